@@ -2582,6 +2582,11 @@ rec {
             packageId = "httparse";
             optional = true;
           }
+          {
+            name = "httpdate";
+            packageId = "httpdate";
+            optional = true;
+          }
           {
             name = "itoa";
             packageId = "itoa";
@@ -2640,7 +2645,7 @@ rec {
           "server" = [ "dep:httpdate" "dep:pin-project-lite" "dep:smallvec" ];
           "tracing" = [ "dep:tracing" ];
         };
-        resolvedDefaultFeatures = [ "client" "default" "http1" ];
+        resolvedDefaultFeatures = [ "client" "default" "http1" "server" ];
       };
       "hyper-http-proxy" = rec {
         crateName = "hyper-http-proxy";
@@ -7249,6 +7254,20 @@ rec {
             packageId = "futures 0.3.31";
             features = [ "compat" ];
           }
+          {
+            name = "http-body-util";
+            packageId = "http-body-util";
+          }
+          {
+            name = "hyper";
+            packageId = "hyper";
+            features = [ "http1" "server" ];
+          }
+          {
+            name = "hyper-util";
+            packageId = "hyper-util";
+            features = [ "tokio" ];
+          }
           {
             name = "pin-project";
             packageId = "pin-project";
@@ -7257,6 +7276,16 @@ rec {
             name = "product-config";
             packageId = "product-config";
           }
+          {
+            name = "rustls";
+            packageId = "rustls";
+            usesDefaultFeatures = false;
+            features = [ "ring" "std" "tls12" ];
+          }
+          {
+            name = "rustls-pemfile";
+            packageId = "rustls-pemfile";
+          }
           {
             name = "semver";
             packageId = "semver";
@@ -7288,6 +7317,11 @@ rec {
             packageId = "tokio";
             features = [ "full" ];
           }
+          {
+            name = "tokio-rustls";
+            packageId = "tokio-rustls";
+            usesDefaultFeatures = false;
+          }
           {
             name = "tracing";
             packageId = "tracing";
@@ -71,6 +71,13 @@ Create the name of the service account to use
 {{- end }}
 {{- end }}
 
+{{/*
+The name of the Secret containing the certificate of the webhooks
+*/}}
+{{- define "operator.webhookSecretName" -}}
+{{- default (printf "%s-webhook-tls" (include "operator.fullname" .)) .Values.webhook.tls.existingSecret }}
+{{- end }}
+
 {{/*
 Labels for Kubernetes objects created by helm test
 */}}
@@ -5,10 +5,12 @@ import java.util.Arrays;
 import org.springframework.boot.CommandLineRunner;
 import org.springframework.boot.SpringApplication;
 import org.springframework.boot.autoconfigure.SpringBootApplication;
+import org.springframework.boot.autoconfigure.security.servlet.UserDetailsServiceAutoConfiguration;
 import org.springframework.context.ApplicationContext;
 import org.springframework.context.annotation.Bean;
 
-@SpringBootApplication
+// The users are configured by the SecurityConfiguration, there must not be a generated default user
+@SpringBootApplication(exclude = UserDetailsServiceAutoConfiguration.class)
 public class Application {
 
 	public static void main(String[] args) {
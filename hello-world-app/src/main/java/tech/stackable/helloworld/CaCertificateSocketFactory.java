@@ -0,0 +1,100 @@
+package tech.stackable.helloworld;
+
+import java.io.IOException;
+import java.io.InputStream;
+import java.net.InetAddress;
+import java.net.Socket;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.security.GeneralSecurityException;
+import java.security.KeyStore;
+import java.security.cert.Certificate;
+import java.security.cert.CertificateFactory;
+
+import javax.net.SocketFactory;
+import javax.net.ssl.SSLContext;
+import javax.net.ssl.SSLSocketFactory;
+import javax.net.ssl.TrustManagerFactory;
+
+/**
+ * Trusts the CA certificates of the LDAP server.
+ *
+ * JNDI instantiates the socket factory via the static {@link #getDefault()}
+ * method, so the trusted certificates have to be configured statically with
+ * {@link #trust(Path)} beforehand.
+ */
+public class CaCertificateSocketFactory extends SSLSocketFactory {
+
+	private static volatile SSLSocketFactory delegate;
+
+	/** Trusts all certificates in the given PEM file */
+	public static void trust(Path caCertFile) throws IOException, GeneralSecurityException {
+		KeyStore trustStore = KeyStore.getInstance(KeyStore.getDefaultType());
+		trustStore.load(null, null);
+		try (InputStream in = Files.newInputStream(caCertFile)) {
+			int index = 0;
+			for (Certificate certificate : CertificateFactory.getInstance("X.509").generateCertificates(in)) {
+				trustStore.setCertificateEntry("ca-" + index++, certificate);
+			}
+		}
+
+		TrustManagerFactory trustManagerFactory = TrustManagerFactory
+				.getInstance(TrustManagerFactory.getDefaultAlgorithm());
+		trustManagerFactory.init(trustStore);
+		SSLContext sslContext = SSLContext.getInstance("TLS");
+		sslContext.init(null, trustManagerFactory.getTrustManagers(), null);
+		delegate = sslContext.getSocketFactory();
+	}
+
+	public static SocketFactory getDefault() {
+		return new CaCertificateSocketFactory();
+	}
+
+	private static SSLSocketFactory delegate() {
+		if (delegate == null) {
+			throw new IllegalStateException("The trusted CA certificates were not configured");
+		}
+		return delegate;
+	}
+
+	@Override
+	public String[] getDefaultCipherSuites() {
+		return delegate().getDefaultCipherSuites();
+	}
+
+	@Override
+	public String[] getSupportedCipherSuites() {
+		return delegate().getSupportedCipherSuites();
+	}
+
+	@Override
+	public Socket createSocket() throws IOException {
+		return delegate().createSocket();
+	}
+
+	@Override
+	public Socket createSocket(Socket socket, String host, int port, boolean autoClose) throws IOException {
+		return delegate().createSocket(socket, host, port, autoClose);
+	}
+
+	@Override
+	public Socket createSocket(String host, int port) throws IOException {
+		return delegate().createSocket(host, port);
+	}
+
+	@Override
+	public Socket createSocket(String host, int port, InetAddress localHost, int localPort) throws IOException {
+		return delegate().createSocket(host, port, localHost, localPort);
+	}
+
+	@Override
+	public Socket createSocket(InetAddress host, int port) throws IOException {
+		return delegate().createSocket(host, port);
+	}
+
+	@Override
+	public Socket createSocket(InetAddress address, int port, InetAddress localAddress, int localPort)
+			throws IOException {
+		return delegate().createSocket(address, port, localAddress, localPort);
+	}
+}
@@ -5,6 +5,7 @@ import org.slf4j.LoggerFactory;
 import org.springframework.beans.factory.annotation.Value;
 import org.springframework.web.bind.annotation.GetMapping;
 import org.springframework.web.bind.annotation.RestController;
+import org.springframework.web.util.HtmlUtils;
 
 @RestController
 public class HelloController {
@@ -20,6 +21,7 @@ public class HelloController {
 	@GetMapping("/")
 	public String index() {
 		log.info("Received an HTTP request.");
-		return "<h1 style=\"color:" + color +"\">Hello " + recipient + "!</h1>";
+		// The operator validates the greeting, escaping is only a second line of defense
+		return "<h1 style=\"color:" + HtmlUtils.htmlEscape(color) + "\">Hello " + HtmlUtils.htmlEscape(recipient) + "!</h1>";
 	}
 }
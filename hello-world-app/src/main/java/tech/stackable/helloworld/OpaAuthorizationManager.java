@@ -0,0 +1,82 @@
+package tech.stackable.helloworld;
+
+import java.net.URI;
+import java.util.HashMap;
+import java.util.Map;
+import java.util.function.Supplier;
+
+import org.slf4j.Logger;
+import org.slf4j.LoggerFactory;
+import org.springframework.http.MediaType;
+import org.springframework.security.authentication.AnonymousAuthenticationToken;
+import org.springframework.security.authorization.AuthorizationDecision;
+import org.springframework.security.authorization.AuthorizationManager;
+import org.springframework.security.core.Authentication;
+import org.springframework.security.web.access.intercept.RequestAuthorizationContext;
+import org.springframework.web.client.RestClient;
+import org.springframework.web.client.RestClientException;
+
+import com.fasterxml.jackson.annotation.JsonIgnoreProperties;
+
+import jakarta.servlet.http.HttpServletRequest;
+
+/**
+ * Asks OPA whether a request is allowed.
+ *
+ * The rule configured in `greeting.authorization.opa.url` receives the input
+ * `{"user": ..., "method": ..., "path": ...}`, where the user is `null` for
+ * anonymous requests. Requests are denied unless the rule evaluates to `true`,
+ * this includes undefined rules and OPA being unreachable.
+ */
+public class OpaAuthorizationManager implements AuthorizationManager<RequestAuthorizationContext> {
+
+	private final Logger log = LoggerFactory.getLogger(this.getClass());
+
+	private final RestClient restClient;
+
+	private final URI ruleUrl;
+
+	public OpaAuthorizationManager(RestClient restClient, URI ruleUrl) {
+		this.restClient = restClient;
+		this.ruleUrl = ruleUrl;
+	}
+
+	@Override
+	public AuthorizationDecision check(Supplier<Authentication> authentication, RequestAuthorizationContext context) {
+		return new AuthorizationDecision(isAllowed(authentication.get(), context.getRequest()));
+	}
+
+	boolean isAllowed(Authentication authentication, HttpServletRequest request) {
+		// Map.of does not allow the null user of anonymous requests
+		Map<String, Object> input = new HashMap<>();
+		input.put("user", user(authentication));
+		input.put("method", request.getMethod());
+		input.put("path", request.getRequestURI());
+
+		try {
+			OpaResponse response = restClient.post()
+					.uri(ruleUrl)
+					.contentType(MediaType.APPLICATION_JSON)
+					.body(Map.of("input", input))
+					.retrieve()
+					.body(OpaResponse.class);
+			return response != null && Boolean.TRUE.equals(response.result());
+		} catch (RestClientException e) {
+			log.warn("Denying the request, because OPA could not be queried", e);
+			return false;
+		}
+	}
+
+	private static String user(Authentication authentication) {
+		if (authentication == null || authentication instanceof AnonymousAuthenticationToken
+				|| !authentication.isAuthenticated()) {
+			return null;
+		}
+		return authentication.getName();
+	}
+
+	/** The result is missing if the rule is undefined */
+	@JsonIgnoreProperties(ignoreUnknown = true)
+	record OpaResponse(Object result) {
+	}
+}
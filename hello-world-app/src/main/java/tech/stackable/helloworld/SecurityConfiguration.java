@@ -0,0 +1,177 @@
+package tech.stackable.helloworld;
+
+import java.io.IOException;
+import java.net.URI;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.security.GeneralSecurityException;
+import java.time.Duration;
+import java.util.List;
+import java.util.Map;
+import java.util.stream.Stream;
+
+import org.springframework.beans.factory.annotation.Value;
+import org.springframework.boot.actuate.autoconfigure.security.servlet.EndpointRequest;
+import org.springframework.context.annotation.Bean;
+import org.springframework.context.annotation.Configuration;
+import org.springframework.http.client.SimpleClientHttpRequestFactory;
+import org.springframework.ldap.core.support.LdapContextSource;
+import org.springframework.security.authentication.ProviderManager;
+import org.springframework.security.authorization.AuthenticatedAuthorizationManager;
+import org.springframework.security.authorization.AuthorizationManagers;
+import org.springframework.security.config.Customizer;
+import org.springframework.security.config.annotation.web.builders.HttpSecurity;
+import org.springframework.security.config.annotation.web.configuration.EnableWebSecurity;
+import org.springframework.security.core.userdetails.User;
+import org.springframework.security.core.userdetails.UserDetails;
+import org.springframework.security.ldap.authentication.BindAuthenticator;
+import org.springframework.security.ldap.authentication.LdapAuthenticationProvider;
+import org.springframework.security.ldap.search.FilterBasedLdapUserSearch;
+import org.springframework.security.provisioning.InMemoryUserDetailsManager;
+import org.springframework.security.web.SecurityFilterChain;
+import org.springframework.web.client.RestClient;
+
+/**
+ * Enforces the AuthenticationClass the operator configured in the
+ * `greeting.authentication.*` properties and the OPA authorization configured
+ * in `greeting.authorization.opa.url`.
+ *
+ * The actuator endpoints stay reachable without credentials, so that the
+ * probes and the metrics scraping keep working.
+ */
+@Configuration
+@EnableWebSecurity
+public class SecurityConfiguration {
+
+	@Value("${greeting.authentication.type:none}")
+	String authenticationType;
+
+	@Value("${greeting.authentication.static.users-directory:}")
+	String staticUsersDirectory;
+
+	@Value("${spring.ldap.urls:}")
+	String[] ldapUrls;
+
+	@Value("${spring.ldap.base:}")
+	String ldapBase;
+
+	@Value("${greeting.authentication.ldap.search-filter:}")
+	String ldapSearchFilter;
+
+	@Value("${greeting.authentication.ldap.uid-field:uid}")
+	String ldapUidField;
+
+	@Value("${greeting.authentication.ldap.bind-user-file:}")
+	String ldapBindUserFile;
+
+	@Value("${greeting.authentication.ldap.bind-password-file:}")
+	String ldapBindPasswordFile;
+
+	@Value("${greeting.authentication.ldap.ca-cert-file:}")
+	String ldapCaCertFile;
+
+	@Value("${greeting.authorization.opa.url:}")
+	String opaUrl;
+
+	@Bean
+	public SecurityFilterChain securityFilterChain(HttpSecurity http, RestClient.Builder restClientBuilder)
+			throws Exception {
+		boolean authenticationEnabled = !authenticationType.equals("none");
+
+		switch (authenticationType) {
+			case "none" -> {
+			}
+			case "static" -> http
+					.userDetailsService(staticUsers())
+					.httpBasic(Customizer.withDefaults());
+			case "ldap" -> http
+					.authenticationManager(new ProviderManager(ldapAuthenticationProvider()))
+					.httpBasic(Customizer.withDefaults());
+			case "oidc" -> http.oauth2Login(Customizer.withDefaults());
+			default -> throw new IllegalArgumentException(
+					"Unsupported authentication type [" + authenticationType + "]");
+		}
+
+		http.authorizeHttpRequests(requests -> {
+			requests.requestMatchers(EndpointRequest.toAnyEndpoint()).permitAll();
+			if (!opaUrl.isEmpty()) {
+				OpaAuthorizationManager opa = opaAuthorizationManager(restClientBuilder);
+				// Unauthenticated requests are challenged for credentials before OPA is asked
+				requests.anyRequest().access(authenticationEnabled
+						? AuthorizationManagers.allOf(AuthenticatedAuthorizationManager.authenticated(), opa)
+						: opa);
+			} else if (authenticationEnabled) {
+				requests.anyRequest().authenticated();
+			} else {
+				requests.anyRequest().permitAll();
+			}
+		});
+
+		return http.build();
+	}
+
+	OpaAuthorizationManager opaAuthorizationManager(RestClient.Builder restClientBuilder) {
+		SimpleClientHttpRequestFactory requestFactory = new SimpleClientHttpRequestFactory();
+		requestFactory.setConnectTimeout(Duration.ofSeconds(5));
+		requestFactory.setReadTimeout(Duration.ofSeconds(5));
+
+		return new OpaAuthorizationManager(
+				restClientBuilder.requestFactory(requestFactory).build(),
+				URI.create(opaUrl));
+	}
+
+	/**
+	 * The Secret of the static provider is mounted as a directory containing
+	 * one file per user, named after the user and containing the password.
+	 */
+	InMemoryUserDetailsManager staticUsers() throws IOException {
+		List<UserDetails> users;
+		try (Stream<Path> files = Files.list(Path.of(staticUsersDirectory))) {
+			users = files
+					// Skip the hidden files and directories Kubernetes uses to update the Secret atomically
+					.filter(file -> !file.getFileName().toString().startsWith("."))
+					.filter(Files::isRegularFile)
+					.map(file -> User.withUsername(file.getFileName().toString())
+							.password("{noop}" + readFile(file))
+							.roles("USER")
+							.build())
+					.toList();
+		}
+		return new InMemoryUserDetailsManager(users);
+	}
+
+	LdapAuthenticationProvider ldapAuthenticationProvider() throws IOException, GeneralSecurityException {
+		LdapContextSource contextSource = new LdapContextSource();
+		contextSource.setUrls(ldapUrls);
+		contextSource.setBase(ldapBase);
+		if (ldapBindUserFile.isEmpty()) {
+			contextSource.setAnonymousReadOnly(true);
+		} else {
+			contextSource.setUserDn(readFile(Path.of(ldapBindUserFile)));
+			contextSource.setPassword(readFile(Path.of(ldapBindPasswordFile)));
+		}
+		if (!ldapCaCertFile.isEmpty()) {
+			CaCertificateSocketFactory.trust(Path.of(ldapCaCertFile));
+			contextSource.setBaseEnvironmentProperties(Map.of(
+					"java.naming.ldap.factory.socket", CaCertificateSocketFactory.class.getName()));
+		}
+		contextSource.afterPropertiesSet();
+
+		String userFilter = "(" + ldapUidField + "={0})";
+		if (!ldapSearchFilter.isEmpty()) {
+			userFilter = "(&" + userFilter + ldapSearchFilter + ")";
+		}
+		BindAuthenticator authenticator = new BindAuthenticator(contextSource);
+		authenticator.setUserSearch(new FilterBasedLdapUserSearch("", userFilter, contextSource));
+
+		return new LdapAuthenticationProvider(authenticator);
+	}
+
+	private static String readFile(Path file) {
+		try {
+			return Files.readString(file).strip();
+		} catch (IOException e) {
+			throw new IllegalStateException("Failed to read [" + file + "]", e);
+		}
+	}
+}
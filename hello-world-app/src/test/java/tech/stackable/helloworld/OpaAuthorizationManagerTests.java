@@ -0,0 +1,69 @@
+package tech.stackable.helloworld;
+
+import static org.assertj.core.api.Assertions.assertThat;
+import static org.springframework.test.web.client.match.MockRestRequestMatchers.content;
+import static org.springframework.test.web.client.match.MockRestRequestMatchers.method;
+import static org.springframework.test.web.client.match.MockRestRequestMatchers.requestTo;
+import static org.springframework.test.web.client.response.MockRestResponseCreators.withServerError;
+import static org.springframework.test.web.client.response.MockRestResponseCreators.withSuccess;
+
+import java.net.URI;
+
+import org.junit.jupiter.api.BeforeEach;
+import org.junit.jupiter.api.Test;
+import org.junit.jupiter.params.ParameterizedTest;
+import org.junit.jupiter.params.provider.ValueSource;
+import org.springframework.http.HttpMethod;
+import org.springframework.http.MediaType;
+import org.springframework.mock.web.MockHttpServletRequest;
+import org.springframework.security.authentication.TestingAuthenticationToken;
+import org.springframework.test.web.client.MockRestServiceServer;
+import org.springframework.web.client.RestClient;
+
+class OpaAuthorizationManagerTests {
+
+	static final String RULE_URL = "http://opa:8081/v1/data/hello/allow";
+
+	MockRestServiceServer opa;
+
+	OpaAuthorizationManager authorizationManager;
+
+	@BeforeEach
+	void setUp() {
+		RestClient.Builder builder = RestClient.builder();
+		opa = MockRestServiceServer.bindTo(builder).build();
+		authorizationManager = new OpaAuthorizationManager(builder.build(), URI.create(RULE_URL));
+	}
+
+	boolean isAllowed() {
+		return authorizationManager.isAllowed(
+				new TestingAuthenticationToken("alice", "secret", "ROLE_USER"),
+				new MockHttpServletRequest("GET", "/"));
+	}
+
+	@Test
+	void requestIsAllowedIfTheRuleIsTrue() {
+		opa.expect(requestTo(RULE_URL))
+				.andExpect(method(HttpMethod.POST))
+				.andExpect(content().json("{\"input\": {\"user\": \"alice\", \"method\": \"GET\", \"path\": \"/\"}}"))
+				.andRespond(withSuccess("{\"decision_id\": \"1\", \"result\": true}", MediaType.APPLICATION_JSON));
+
+		assertThat(isAllowed()).isTrue();
+		opa.verify();
+	}
+
+	@ParameterizedTest
+	@ValueSource(strings = { "{}", "{\"result\": false}", "{\"result\": \"true\"}", "{\"result\": {}}" })
+	void requestIsDeniedUnlessTheRuleIsTrue(String response) {
+		opa.expect(requestTo(RULE_URL)).andRespond(withSuccess(response, MediaType.APPLICATION_JSON));
+
+		assertThat(isAllowed()).isFalse();
+	}
+
+	@Test
+	void requestIsDeniedIfOpaFails() {
+		opa.expect(requestTo(RULE_URL)).andRespond(withServerError());
+
+		assertThat(isAllowed()).isFalse();
+	}
+}
@@ -0,0 +1,54 @@
+package tech.stackable.helloworld;
+
+import static org.springframework.security.test.web.servlet.request.SecurityMockMvcRequestPostProcessors.httpBasic;
+import static org.springframework.test.web.servlet.request.MockMvcRequestBuilders.get;
+import static org.springframework.test.web.servlet.result.MockMvcResultMatchers.status;
+
+import java.io.IOException;
+import java.nio.file.Files;
+import java.nio.file.Path;
+
+import org.junit.jupiter.api.Test;
+import org.springframework.beans.factory.annotation.Autowired;
+import org.springframework.boot.test.autoconfigure.web.servlet.AutoConfigureMockMvc;
+import org.springframework.boot.test.context.SpringBootTest;
+import org.springframework.test.context.DynamicPropertyRegistry;
+import org.springframework.test.context.DynamicPropertySource;
+import org.springframework.test.web.servlet.MockMvc;
+
+@SpringBootTest
+@AutoConfigureMockMvc
+class StaticAuthenticationTests {
+
+	@Autowired
+	MockMvc mockMvc;
+
+	@DynamicPropertySource
+	static void staticUsers(DynamicPropertyRegistry registry) throws IOException {
+		Path usersDirectory = Files.createTempDirectory("users");
+		Files.writeString(usersDirectory.resolve("alice"), "secret\n");
+
+		registry.add("greeting.authentication.type", () -> "static");
+		registry.add("greeting.authentication.static.users-directory", usersDirectory::toString);
+	}
+
+	@Test
+	void requestWithoutCredentialsIsUnauthorized() throws Exception {
+		mockMvc.perform(get("/")).andExpect(status().isUnauthorized());
+	}
+
+	@Test
+	void requestWithWrongPasswordIsUnauthorized() throws Exception {
+		mockMvc.perform(get("/").with(httpBasic("alice", "wrong"))).andExpect(status().isUnauthorized());
+	}
+
+	@Test
+	void requestWithCredentialsIsGreeted() throws Exception {
+		mockMvc.perform(get("/").with(httpBasic("alice", "secret"))).andExpect(status().isOk());
+	}
+
+	@Test
+	void actuatorIsReachableWithoutCredentials() throws Exception {
+		mockMvc.perform(get("/actuator/health")).andExpect(status().isOk());
+	}
+}
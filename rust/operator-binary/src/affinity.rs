@@ -39,16 +39,17 @@ mod tests {
     #[case(HelloRole::Server)]
     fn test_affinity_defaults(#[case] role: HelloRole) {
         let input = r#"
-        apiVersion: hello-world.stackable.tech/v1alpha1
+        apiVersion: hello-world.stackable.tech/v1alpha2
         kind: HelloCluster
         metadata:
           name: hello-world
         spec:
           image:
             productVersion: 0.1.0
-          recipient: "Stackable"
-          color: "blue"
           clusterConfig:
+              greeting:
+                recipient: Stackable
+                color: blue
               listenerClass: external-unstable
           servers:
             roleGroups:
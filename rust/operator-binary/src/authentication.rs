@@ -0,0 +1,551 @@
+//! Resolves the AuthenticationClass referenced by a [`HelloCluster`] and translates it into the
+//! Spring Security configuration, Volumes and environment variables of the servers.
+//!
+//! The `greeting.authentication.*` properties are enforced by the `SecurityConfiguration` of the
+//! hello-world-app.
+use std::collections::BTreeMap;
+
+use snafu::{ensure, ResultExt, Snafu};
+use stackable_operator::{
+    builder::pod::{container::ContainerBuilder, volume::VolumeBuilder, PodBuilder},
+    client::Client,
+    commons::authentication::{
+        ldap, oidc, static_, AuthenticationClass, AuthenticationClassProvider,
+        ClientAuthenticationDetails,
+    },
+    kube::runtime::reflector::ObjectRef,
+};
+
+use crate::crd::HelloCluster;
+
+pub const STACKABLE_USERS_DIR: &str = "/stackable/users";
+pub const STACKABLE_USERS_DIR_NAME: &str = "static-users";
+// config properties
+pub const AUTHENTICATION_TYPE: &str = "greeting.authentication.type";
+pub const STATIC_USERS_DIRECTORY: &str = "greeting.authentication.static.users-directory";
+pub const LDAP_URLS: &str = "spring.ldap.urls";
+pub const LDAP_BASE: &str = "spring.ldap.base";
+pub const LDAP_SEARCH_FILTER: &str = "greeting.authentication.ldap.search-filter";
+pub const LDAP_UID_FIELD: &str = "greeting.authentication.ldap.uid-field";
+pub const LDAP_BIND_USER_FILE: &str = "greeting.authentication.ldap.bind-user-file";
+pub const LDAP_BIND_PASSWORD_FILE: &str = "greeting.authentication.ldap.bind-password-file";
+pub const LDAP_CA_CERT_FILE: &str = "greeting.authentication.ldap.ca-cert-file";
+/// The Spring Security registration id of the OpenID Connect client
+const OIDC_REGISTRATION_ID: &str = "oidc";
+const OIDC_DEFAULT_SCOPES: [&str; 3] = ["openid", "email", "profile"];
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to retrieve AuthenticationClass {authentication_class}"))]
+    AuthenticationClassRetrieval {
+        source: stackable_operator::client::Error,
+        authentication_class: ObjectRef<AuthenticationClass>,
+    },
+
+    #[snafu(display(
+        "only one AuthenticationClass is currently supported, but {count} were provided"
+    ))]
+    MultipleAuthenticationClassesProvided { count: usize },
+
+    #[snafu(display(
+        "the {provider} provider of AuthenticationClass {authentication_class} is not supported, only static, LDAP and OIDC are"
+    ))]
+    AuthenticationProviderNotSupported {
+        authentication_class: ObjectRef<AuthenticationClass>,
+        provider: String,
+    },
+
+    #[snafu(display("invalid OIDC configuration for AuthenticationClass {authentication_class}"))]
+    OidcConfiguration {
+        source: stackable_operator::commons::authentication::Error,
+        authentication_class: ObjectRef<AuthenticationClass>,
+    },
+
+    #[snafu(display("failed to build the LDAP endpoint URL"))]
+    LdapEndpointUrl { source: ldap::Error },
+
+    #[snafu(display("failed to build the OIDC endpoint URL"))]
+    OidcEndpointUrl { source: oidc::Error },
+
+    #[snafu(display("failed to add the LDAP Volumes and VolumeMounts"))]
+    AddLdapVolumes { source: ldap::Error },
+
+    #[snafu(display("failed to add the OIDC TLS Volumes and VolumeMounts"))]
+    AddOidcVolumes {
+        source: stackable_operator::commons::tls_verification::TlsClientDetailsError,
+    },
+
+    #[snafu(display("failed to add the static users Volume"))]
+    AddStaticUsersVolume {
+        source: stackable_operator::builder::pod::Error,
+    },
+
+    #[snafu(display("failed to add the static users VolumeMount"))]
+    AddStaticUsersVolumeMount {
+        source: stackable_operator::builder::pod::container::Error,
+    },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The AuthenticationClass referenced by a [`HelloCluster`], resolved and checked for support
+#[derive(Clone, Debug)]
+pub enum ResolvedAuthenticationClass {
+    Static(static_::AuthenticationProvider),
+    Ldap(ldap::AuthenticationProvider),
+    Oidc {
+        provider: oidc::AuthenticationProvider,
+        client_options: oidc::ClientAuthenticationOptions,
+    },
+}
+
+/// Retrieves the AuthenticationClass referenced in `clusterConfig.authentication`, if any.
+///
+/// Only a single AuthenticationClass using the static, LDAP or OIDC provider is supported.
+pub async fn resolve_authentication_class(
+    hello: &HelloCluster,
+    client: &Client,
+) -> Result<Option<ResolvedAuthenticationClass>> {
+    let authentication = &hello.spec.cluster_config.authentication;
+    ensure!(
+        authentication.len() <= 1,
+        MultipleAuthenticationClassesProvidedSnafu {
+            count: authentication.len()
+        }
+    );
+
+    let Some(details) = authentication.first() else {
+        return Ok(None);
+    };
+
+    let auth_class = details.resolve_class(client).await.with_context(|_| {
+        AuthenticationClassRetrievalSnafu {
+            authentication_class: ObjectRef::<AuthenticationClass>::new(
+                details.authentication_class_name(),
+            ),
+        }
+    })?;
+
+    ResolvedAuthenticationClass::new(auth_class, details).map(Some)
+}
+
+impl ResolvedAuthenticationClass {
+    /// Checks that the provider of the AuthenticationClass referenced by `details` is supported
+    fn new(auth_class: AuthenticationClass, details: &ClientAuthenticationDetails) -> Result<Self> {
+        let auth_class_name = details.authentication_class_name();
+        let auth_class_ref = ObjectRef::<AuthenticationClass>::new(auth_class_name);
+
+        match auth_class.spec.provider {
+            AuthenticationClassProvider::Static(provider) => Ok(Self::Static(provider)),
+            AuthenticationClassProvider::Ldap(provider) => Ok(Self::Ldap(provider)),
+            AuthenticationClassProvider::Oidc(provider) => Ok(Self::Oidc {
+                provider,
+                client_options: details
+                    .oidc_or_error(auth_class_name)
+                    .with_context(|_| OidcConfigurationSnafu {
+                        authentication_class: auth_class_ref.clone(),
+                    })?
+                    .clone(),
+            }),
+            AuthenticationClassProvider::Tls(_) => AuthenticationProviderNotSupportedSnafu {
+                authentication_class: auth_class_ref,
+                provider: "TLS",
+            }
+            .fail(),
+            AuthenticationClassProvider::Kerberos(_) => AuthenticationProviderNotSupportedSnafu {
+                authentication_class: auth_class_ref,
+                provider: "Kerberos",
+            }
+            .fail(),
+        }
+    }
+
+    /// The Spring Security settings which are written to the `application.properties`
+    pub fn application_properties(&self) -> Result<BTreeMap<String, String>> {
+        let mut properties = BTreeMap::new();
+
+        match self {
+            Self::Static(_) => {
+                properties.insert(AUTHENTICATION_TYPE.to_owned(), "static".to_owned());
+                properties.insert(
+                    STATIC_USERS_DIRECTORY.to_owned(),
+                    STACKABLE_USERS_DIR.to_owned(),
+                );
+            }
+            Self::Ldap(provider) => {
+                properties.insert(AUTHENTICATION_TYPE.to_owned(), "ldap".to_owned());
+                properties.insert(
+                    LDAP_URLS.to_owned(),
+                    provider
+                        .endpoint_url()
+                        .context(LdapEndpointUrlSnafu)?
+                        .to_string(),
+                );
+                properties.insert(LDAP_BASE.to_owned(), provider.search_base.clone());
+                properties.insert(
+                    LDAP_SEARCH_FILTER.to_owned(),
+                    provider.search_filter.clone(),
+                );
+                properties.insert(
+                    LDAP_UID_FIELD.to_owned(),
+                    provider.ldap_field_names.uid.clone(),
+                );
+                if let Some((user_path, password_path)) = provider.bind_credentials_mount_paths() {
+                    properties.insert(LDAP_BIND_USER_FILE.to_owned(), user_path);
+                    properties.insert(LDAP_BIND_PASSWORD_FILE.to_owned(), password_path);
+                }
+                if let Some(ca_cert_path) = provider.tls.tls_ca_cert_mount_path() {
+                    properties.insert(LDAP_CA_CERT_FILE.to_owned(), ca_cert_path);
+                }
+            }
+            Self::Oidc {
+                provider,
+                client_options,
+            } => {
+                let (client_id_env, client_secret_env) =
+                    oidc::AuthenticationProvider::client_credentials_env_names(
+                        &client_options.client_credentials_secret_ref,
+                    );
+                let registration =
+                    format!("spring.security.oauth2.client.registration.{OIDC_REGISTRATION_ID}");
+                let provider_prefix =
+                    format!("spring.security.oauth2.client.provider.{OIDC_REGISTRATION_ID}");
+                let scopes = OIDC_DEFAULT_SCOPES
+                    .iter()
+                    .map(|scope| scope.to_string())
+                    .chain(provider.scopes.iter().cloned())
+                    .chain(client_options.extra_scopes.iter().cloned())
+                    .collect::<Vec<_>>();
+
+                properties.insert(AUTHENTICATION_TYPE.to_owned(), "oidc".to_owned());
+                properties.insert(
+                    format!("{registration}.client-id"),
+                    format!("${{{client_id_env}}}"),
+                );
+                properties.insert(
+                    format!("{registration}.client-secret"),
+                    format!("${{{client_secret_env}}}"),
+                );
+                properties.insert(
+                    format!("{registration}.authorization-grant-type"),
+                    "authorization_code".to_owned(),
+                );
+                properties.insert(format!("{registration}.scope"), scopes.join(","));
+                properties.insert(
+                    format!("{provider_prefix}.issuer-uri"),
+                    provider
+                        .endpoint_url()
+                        .context(OidcEndpointUrlSnafu)?
+                        .to_string(),
+                );
+                properties.insert(
+                    format!("{provider_prefix}.user-name-attribute"),
+                    provider.principal_claim.clone(),
+                );
+            }
+        }
+
+        Ok(properties)
+    }
+
+    /// Mounts the credentials and CA certificates needed to talk to the authentication provider
+    pub fn add_volumes_and_mounts(
+        &self,
+        pod_builder: &mut PodBuilder,
+        container_builder: &mut ContainerBuilder,
+    ) -> Result<()> {
+        match self {
+            Self::Static(provider) => {
+                pod_builder
+                    .add_volume(
+                        VolumeBuilder::new(STACKABLE_USERS_DIR_NAME)
+                            .with_secret(&provider.user_credentials_secret.name, false)
+                            .build(),
+                    )
+                    .context(AddStaticUsersVolumeSnafu)?;
+                container_builder
+                    .add_volume_mount(STACKABLE_USERS_DIR_NAME, STACKABLE_USERS_DIR)
+                    .context(AddStaticUsersVolumeMountSnafu)?;
+            }
+            Self::Ldap(provider) => {
+                provider
+                    .add_volumes_and_mounts(pod_builder, vec![container_builder])
+                    .context(AddLdapVolumesSnafu)?;
+            }
+            Self::Oidc {
+                provider,
+                client_options,
+            } => {
+                container_builder.add_env_vars(
+                    oidc::AuthenticationProvider::client_credentials_env_var_mounts(
+                        client_options.client_credentials_secret_ref.clone(),
+                    ),
+                );
+                provider
+                    .tls
+                    .add_volumes_and_mounts(pod_builder, vec![container_builder])
+                    .context(AddOidcVolumesSnafu)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+    use serde_json::json;
+
+    const STATIC: &str = r#"
+        static:
+          userCredentialsSecret:
+            name: hello-users
+        "#;
+    const LDAP: &str = r#"
+        ldap:
+          hostname: ldap.example.com
+          port: 389
+          searchBase: ou=users,dc=example,dc=org
+        "#;
+    const LDAP_WITH_BIND_CREDENTIALS_AND_TLS: &str = r#"
+        ldap:
+          hostname: ldap.example.com
+          port: 636
+          searchBase: ou=users,dc=example,dc=org
+          searchFilter: (memberOf=cn=hello,ou=groups,dc=example,dc=org)
+          bindCredentials:
+            secretClass: ldap-bind
+          tls:
+            verification:
+              server:
+                caCert:
+                  secretClass: ldap-tls
+        "#;
+    const OIDC: &str = r#"
+        oidc:
+          hostname: keycloak.example.com
+          port: 8080
+          rootPath: /realms/hello
+          principalClaim: preferred_username
+          scopes:
+            - roles
+          providerHint: Keycloak
+        "#;
+    const OIDC_CLIENT: &str = r#"
+        authenticationClass: hello-auth
+        oidc:
+          clientCredentialsSecret: hello-oidc
+          extraScopes:
+            - groups
+        "#;
+
+    fn resolve(provider: &str, details: &str) -> Result<ResolvedAuthenticationClass> {
+        let provider: serde_json::Value =
+            serde_yaml::from_str(provider).expect("illegal test input");
+        let auth_class: AuthenticationClass = serde_json::from_value(json!({
+            "apiVersion": "authentication.stackable.tech/v1alpha1",
+            "kind": "AuthenticationClass",
+            "metadata": {"name": "hello-auth"},
+            "spec": {"provider": provider},
+        }))
+        .expect("illegal test input");
+        let details = serde_yaml::from_str(details).expect("illegal test input");
+
+        ResolvedAuthenticationClass::new(auth_class, &details)
+    }
+
+    #[rstest]
+    #[case::static_(
+        STATIC,
+        &[
+            ("greeting.authentication.type", "static"),
+            ("greeting.authentication.static.users-directory", "/stackable/users"),
+        ]
+    )]
+    #[case::ldap(
+        LDAP,
+        &[
+            ("greeting.authentication.type", "ldap"),
+            ("spring.ldap.urls", "ldap://ldap.example.com:389"),
+            ("spring.ldap.base", "ou=users,dc=example,dc=org"),
+            ("greeting.authentication.ldap.search-filter", ""),
+            ("greeting.authentication.ldap.uid-field", "uid"),
+        ]
+    )]
+    #[case::ldap_with_bind_credentials_and_tls(
+        LDAP_WITH_BIND_CREDENTIALS_AND_TLS,
+        &[
+            ("greeting.authentication.type", "ldap"),
+            ("spring.ldap.urls", "ldaps://ldap.example.com:636"),
+            ("spring.ldap.base", "ou=users,dc=example,dc=org"),
+            (
+                "greeting.authentication.ldap.search-filter",
+                "(memberOf=cn=hello,ou=groups,dc=example,dc=org)",
+            ),
+            ("greeting.authentication.ldap.uid-field", "uid"),
+            (
+                "greeting.authentication.ldap.bind-user-file",
+                "/stackable/secrets/ldap-bind/user",
+            ),
+            (
+                "greeting.authentication.ldap.bind-password-file",
+                "/stackable/secrets/ldap-bind/password",
+            ),
+            (
+                "greeting.authentication.ldap.ca-cert-file",
+                "/stackable/secrets/ldap-tls/ca.crt",
+            ),
+        ]
+    )]
+    fn test_application_properties(#[case] provider: &str, #[case] expected: &[(&str, &str)]) {
+        let resolved = resolve(provider, "{authenticationClass: hello-auth}").unwrap();
+
+        let expected = expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(resolved.application_properties().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_oidc_application_properties() {
+        let resolved = resolve(OIDC, OIDC_CLIENT).unwrap();
+        let (client_id_env, client_secret_env) =
+            oidc::AuthenticationProvider::client_credentials_env_names("hello-oidc");
+
+        let registration = "spring.security.oauth2.client.registration.oidc";
+        let provider = "spring.security.oauth2.client.provider.oidc";
+        assert_eq!(
+            resolved.application_properties().unwrap(),
+            BTreeMap::from([
+                (AUTHENTICATION_TYPE.to_owned(), "oidc".to_owned()),
+                (
+                    format!("{registration}.client-id"),
+                    format!("${{{client_id_env}}}")
+                ),
+                (
+                    format!("{registration}.client-secret"),
+                    format!("${{{client_secret_env}}}")
+                ),
+                (
+                    format!("{registration}.authorization-grant-type"),
+                    "authorization_code".to_owned()
+                ),
+                (
+                    format!("{registration}.scope"),
+                    "openid,email,profile,roles,groups".to_owned()
+                ),
+                (
+                    format!("{provider}.issuer-uri"),
+                    "http://keycloak.example.com:8080/realms/hello".to_owned()
+                ),
+                (
+                    format!("{provider}.user-name-attribute"),
+                    "preferred_username".to_owned()
+                ),
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case::static_(STATIC, "{authenticationClass: hello-auth}", 1)]
+    #[case::ldap(LDAP, "{authenticationClass: hello-auth}", 0)]
+    #[case::ldap_with_bind_credentials_and_tls(
+        LDAP_WITH_BIND_CREDENTIALS_AND_TLS,
+        "{authenticationClass: hello-auth}",
+        2
+    )]
+    #[case::oidc(OIDC, OIDC_CLIENT, 0)]
+    fn test_volumes_and_mounts(
+        #[case] provider: &str,
+        #[case] details: &str,
+        #[case] expected_mounts: usize,
+    ) {
+        let resolved = resolve(provider, details).unwrap();
+        let mut pod_builder = PodBuilder::new();
+        let mut container_builder = ContainerBuilder::new("hello").unwrap();
+
+        resolved
+            .add_volumes_and_mounts(&mut pod_builder, &mut container_builder)
+            .unwrap();
+
+        let volumes = pod_builder
+            .build_template()
+            .spec
+            .and_then(|spec| spec.volumes)
+            .unwrap_or_default();
+        let mounts = container_builder.build().volume_mounts.unwrap_or_default();
+        assert_eq!(mounts.len(), expected_mounts);
+        assert_eq!(volumes.len(), expected_mounts);
+        for mount in &mounts {
+            assert!(
+                volumes.iter().any(|volume| volume.name == mount.name),
+                "the VolumeMount {} has no Volume",
+                mount.name
+            );
+        }
+        // The files referenced in the application.properties must be mounted
+        for (key, path) in resolved.application_properties().unwrap() {
+            if key.ends_with("-file") || key.ends_with("-directory") {
+                assert!(
+                    mounts
+                        .iter()
+                        .any(|mount| path.starts_with(&mount.mount_path)),
+                    "{key}={path} is not mounted"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_oidc_client_credentials_env_vars() {
+        let resolved = resolve(OIDC, OIDC_CLIENT).unwrap();
+        let mut pod_builder = PodBuilder::new();
+        let mut container_builder = ContainerBuilder::new("hello").unwrap();
+
+        resolved
+            .add_volumes_and_mounts(&mut pod_builder, &mut container_builder)
+            .unwrap();
+
+        let (client_id_env, client_secret_env) =
+            oidc::AuthenticationProvider::client_credentials_env_names("hello-oidc");
+        let env_names = container_builder
+            .build()
+            .env
+            .unwrap_or_default()
+            .into_iter()
+            .map(|env| env.name)
+            .collect::<Vec<_>>();
+        assert!(env_names.contains(&client_id_env));
+        assert!(env_names.contains(&client_secret_env));
+    }
+
+    #[rstest]
+    #[case::tls("tls: {clientCertSecretClass: hello-client-certs}", "TLS")]
+    #[case::kerberos("kerberos: {kerberosSecretClass: kerberos}", "Kerberos")]
+    fn test_unsupported_providers(#[case] provider: &str, #[case] expected_provider: &str) {
+        let result = resolve(provider, "{authenticationClass: hello-auth}");
+
+        assert!(
+            matches!(
+                &result,
+                Err(Error::AuthenticationProviderNotSupported { provider, .. })
+                    if provider == expected_provider
+            ),
+            "unexpected result {result:?}"
+        );
+    }
+
+    #[test]
+    fn test_oidc_requires_client_options() {
+        let result = resolve(OIDC, "{authenticationClass: hello-auth}");
+
+        assert!(
+            matches!(&result, Err(Error::OidcConfiguration { .. })),
+            "unexpected result {result:?}"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Scales role groups automatically with [`HorizontalPodAutoscaler`]s.
+//!
+//! A [`HorizontalPodAutoscaler`] is created for every role group which declares `autoscaling`.
+//! The replicas of the corresponding [`StatefulSet`] or [`Deployment`] are left unset, so that the
+//! operator does not reset the replicas chosen by the autoscaler.
+//!
+//! [`HorizontalPodAutoscaler`]s are not supported by the `ClusterResources` of the
+//! stackable-operator, so they are applied by this module and cleaned up by [`crate::orphans`]
+//! instead.
+use std::collections::BTreeSet;
+
+use snafu::{ensure, ResultExt, Snafu};
+use stackable_operator::{
+    builder::meta::ObjectMetaBuilder,
+    client::Client,
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::api::{
+        apps::v1::{Deployment, StatefulSet},
+        autoscaling::v2::{
+            CrossVersionObjectReference, HorizontalPodAutoscaler, HorizontalPodAutoscalerSpec,
+            MetricSpec, MetricTarget, ResourceMetricSource,
+        },
+    },
+    kube::{Resource, ResourceExt},
+    kvp::Labels,
+    role_utils::RoleGroupRef,
+};
+
+use crate::{
+    controller::build_recommended_labels,
+    crd::{AutoscalingConfig, HelloCluster, HelloWorkload},
+    orphans::delete_orphaned_resources,
+    OPERATOR_NAME,
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object is missing metadata to build owner reference"))]
+    ObjectMissingMetadataForOwnerRef {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build Metadata"))]
+    MetadataBuild {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display(
+        "the autoscaling of {rolegroup} is invalid: minReplicas ({min_replicas}) must be at least 1 and at most maxReplicas ({max_replicas})"
+    ))]
+    InvalidReplicaRange {
+        rolegroup: RoleGroupRef<HelloCluster>,
+        min_replicas: u16,
+        max_replicas: u16,
+    },
+
+    #[snafu(display("the autoscaling of {rolegroup} is invalid: targetCpuUtilizationPercentage must be greater than 0"))]
+    InvalidTargetCpuUtilization {
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to apply HorizontalPodAutoscaler {name}"))]
+    ApplyHorizontalPodAutoscaler {
+        source: stackable_operator::client::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to delete the orphaned HorizontalPodAutoscalers"))]
+    DeleteOrphanedHorizontalPodAutoscalers { source: crate::orphans::Error },
+}
+
+/// Checks the bounds of the `autoscaling` of a role group, which the Kubernetes API server would
+/// only reject when the [`HorizontalPodAutoscaler`] is applied
+pub fn validate_autoscaling(
+    rolegroup: &RoleGroupRef<HelloCluster>,
+    autoscaling: &AutoscalingConfig,
+) -> Result<(), Error> {
+    ensure!(
+        autoscaling.min_replicas >= 1 && autoscaling.min_replicas <= autoscaling.max_replicas,
+        InvalidReplicaRangeSnafu {
+            rolegroup: rolegroup.clone(),
+            min_replicas: autoscaling.min_replicas,
+            max_replicas: autoscaling.max_replicas,
+        }
+    );
+    ensure!(
+        autoscaling.target_cpu_utilization_percentage > 0,
+        InvalidTargetCpuUtilizationSnafu {
+            rolegroup: rolegroup.clone(),
+        }
+    );
+    Ok(())
+}
+
+/// The [`HorizontalPodAutoscaler`] of a role group targets the workload of the role group, which
+/// has the same name.
+pub fn build_rolegroup_horizontal_pod_autoscaler(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    rolegroup: &RoleGroupRef<HelloCluster>,
+    workload: &HelloWorkload,
+    autoscaling: &AutoscalingConfig,
+) -> Result<HorizontalPodAutoscaler, Error> {
+    Ok(HorizontalPodAutoscaler {
+        metadata: ObjectMetaBuilder::new()
+            .name_and_namespace(hello)
+            .name(rolegroup.object_name())
+            .ownerreference_from_resource(hello, None, Some(true))
+            .context(ObjectMissingMetadataForOwnerRefSnafu)?
+            .with_recommended_labels(build_recommended_labels(
+                hello,
+                &resolved_product_image.app_version_label,
+                &rolegroup.role,
+                &rolegroup.role_group,
+            ))
+            .context(MetadataBuildSnafu)?
+            .build(),
+        spec: Some(HorizontalPodAutoscalerSpec {
+            scale_target_ref: scale_target_ref(rolegroup, workload),
+            min_replicas: Some(autoscaling.min_replicas.into()),
+            max_replicas: autoscaling.max_replicas.into(),
+            metrics: Some(vec![MetricSpec {
+                type_: "Resource".to_owned(),
+                resource: Some(ResourceMetricSource {
+                    name: "cpu".to_owned(),
+                    target: MetricTarget {
+                        type_: "Utilization".to_owned(),
+                        average_utilization: Some(
+                            autoscaling.target_cpu_utilization_percentage.into(),
+                        ),
+                        ..MetricTarget::default()
+                    },
+                }),
+                ..MetricSpec::default()
+            }]),
+            ..HorizontalPodAutoscalerSpec::default()
+        }),
+        status: None,
+    })
+}
+
+fn scale_target_ref(
+    rolegroup: &RoleGroupRef<HelloCluster>,
+    workload: &HelloWorkload,
+) -> CrossVersionObjectReference {
+    let (api_version, kind) = match workload {
+        HelloWorkload::StatefulSet => (StatefulSet::api_version(&()), StatefulSet::kind(&())),
+        HelloWorkload::Deployment => (Deployment::api_version(&()), Deployment::kind(&())),
+    };
+
+    CrossVersionObjectReference {
+        api_version: Some(api_version.into_owned()),
+        kind: kind.into_owned(),
+        name: rolegroup.object_name(),
+    }
+}
+
+/// Applies the given [`HorizontalPodAutoscaler`]s and deletes all other ones of the cluster, e.g.
+/// because the role group or its `autoscaling` was removed.
+///
+/// Nothing is changed while the reconciliation is paused. All [`HorizontalPodAutoscaler`]s are
+/// deleted while the cluster is stopped, so that they do not scale the role groups up again.
+pub async fn apply_horizontal_pod_autoscalers(
+    client: &Client,
+    hello: &HelloCluster,
+    cluster_labels: &Labels,
+    autoscalers: Vec<HorizontalPodAutoscaler>,
+) -> Result<(), Error> {
+    let cluster_operation = &hello.spec.cluster_operation;
+    if cluster_operation.reconciliation_paused {
+        return Ok(());
+    }
+    let autoscalers = if cluster_operation.stopped {
+        Vec::new()
+    } else {
+        autoscalers
+    };
+
+    let mut desired_names = BTreeSet::new();
+    for autoscaler in &autoscalers {
+        let name = autoscaler.name_any();
+        client
+            .apply_patch(OPERATOR_NAME, autoscaler, autoscaler)
+            .await
+            .with_context(|_| ApplyHorizontalPodAutoscalerSnafu { name: name.clone() })?;
+        desired_names.insert(name);
+    }
+
+    delete_orphaned_resources::<HorizontalPodAutoscaler>(
+        client,
+        hello,
+        cluster_labels,
+        &desired_names,
+    )
+    .await
+    .context(DeleteOrphanedHorizontalPodAutoscalersSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+    use stackable_operator::kube::runtime::reflector::ObjectRef;
+
+    fn build(
+        workload: HelloWorkload,
+        min_replicas: u16,
+        max_replicas: u16,
+    ) -> Result<HorizontalPodAutoscaler, Error> {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+          uid: 3f1b8d5e-2a57-4c0e-9d64-0a8f1e7b6c21
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+          servers:
+            roleGroups:
+              default:
+                replicas: 1
+        "#;
+        let hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+        let resolved_product_image = hello
+            .spec
+            .image
+            .resolve("hello", crate::built_info::PKG_VERSION);
+
+        build_rolegroup_horizontal_pod_autoscaler(
+            &hello,
+            &resolved_product_image,
+            &hello.server_rolegroup_ref("default"),
+            &workload,
+            &AutoscalingConfig {
+                min_replicas,
+                max_replicas,
+                target_cpu_utilization_percentage: 70,
+            },
+        )
+    }
+
+    #[rstest]
+    #[case::statefulset(HelloWorkload::StatefulSet, "StatefulSet")]
+    #[case::deployment(HelloWorkload::Deployment, "Deployment")]
+    fn test_autoscaler_targets_the_workload(
+        #[case] workload: HelloWorkload,
+        #[case] expected_kind: &str,
+    ) {
+        let spec = build(workload, 2, 5).unwrap().spec.unwrap();
+
+        assert_eq!(
+            spec.scale_target_ref,
+            CrossVersionObjectReference {
+                api_version: Some("apps/v1".to_owned()),
+                kind: expected_kind.to_owned(),
+                name: "hello-world-server-default".to_owned(),
+            }
+        );
+        assert_eq!(spec.min_replicas, Some(2));
+        assert_eq!(spec.max_replicas, 5);
+        assert_eq!(
+            spec.metrics.unwrap()[0]
+                .resource
+                .as_ref()
+                .unwrap()
+                .target
+                .average_utilization,
+            Some(70)
+        );
+    }
+
+    fn validate(
+        min_replicas: u16,
+        max_replicas: u16,
+        target_cpu_utilization_percentage: u16,
+    ) -> Result<(), Error> {
+        validate_autoscaling(
+            &RoleGroupRef {
+                cluster: ObjectRef::new("hello-world").within("default"),
+                role: "server".to_owned(),
+                role_group: "default".to_owned(),
+            },
+            &AutoscalingConfig {
+                min_replicas,
+                max_replicas,
+                target_cpu_utilization_percentage,
+            },
+        )
+    }
+
+    #[rstest]
+    #[case::single_replica(1, 1)]
+    #[case::range(2, 5)]
+    fn test_valid_autoscaling(#[case] min_replicas: u16, #[case] max_replicas: u16) {
+        assert!(validate(min_replicas, max_replicas, 80).is_ok());
+    }
+
+    #[rstest]
+    #[case::no_replicas(0, 3)]
+    #[case::min_above_max(4, 3)]
+    fn test_invalid_replica_range(#[case] min_replicas: u16, #[case] max_replicas: u16) {
+        assert!(matches!(
+            validate(min_replicas, max_replicas, 80),
+            Err(Error::InvalidReplicaRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_target_cpu_utilization() {
+        assert!(matches!(
+            validate(1, 3, 0),
+            Err(Error::InvalidTargetCpuUtilization { .. })
+        ));
+    }
+}
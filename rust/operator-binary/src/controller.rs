@@ -2,30 +2,43 @@
 use std::{
     borrow::Cow,
     collections::{BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+    iter,
     sync::Arc,
     time::Duration,
 };
 
 use const_format::concatcp;
+use fnv::FnvHasher;
 use product_config::{
     self, types::PropertyNameKind, writer::to_java_properties_string, ProductConfigManager,
 };
-use snafu::{OptionExt, ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use stackable_operator::{
     builder::{
         self,
         configmap::ConfigMapBuilder,
         meta::ObjectMetaBuilder,
-        pod::{container::ContainerBuilder, resources::ResourceRequirementsBuilder, PodBuilder},
+        pod::{
+            container::ContainerBuilder,
+            resources::ResourceRequirementsBuilder,
+            volume::{SecretFormat, SecretOperatorVolumeSourceBuilder, VolumeBuilder},
+            PodBuilder,
+        },
     },
+    client::Client,
     cluster_resources::{ClusterResourceApplyStrategy, ClusterResources},
-    commons::{product_image_selection::ResolvedProductImage, rbac::build_rbac_resources},
+    commons::{
+        opa::OpaApiVersion, product_image_selection::ResolvedProductImage,
+        rbac::build_rbac_resources,
+    },
     k8s_openapi::{
         api::{
-            apps::v1::{StatefulSet, StatefulSetSpec},
+            apps::v1::{Deployment, DeploymentSpec, StatefulSet, StatefulSetSpec},
+            autoscaling::v2::HorizontalPodAutoscaler,
             core::v1::{
-                ConfigMap, ConfigMapVolumeSource, EmptyDirVolumeSource, Probe, Service,
-                ServicePort, ServiceSpec, TCPSocketAction, Volume,
+                ConfigMap, ConfigMapVolumeSource, EmptyDirVolumeSource, HTTPGetAction, Probe,
+                Service, ServicePort, ServiceSpec, Volume,
             },
         },
         apimachinery::pkg::{apis::meta::v1::LabelSelector, util::intstr::IntOrString},
@@ -36,10 +49,13 @@ use stackable_operator::{
         runtime::controller::Action,
         Resource, ResourceExt,
     },
-    kvp::{Labels, ObjectLabels},
+    kvp::{Annotation, Label, Labels, ObjectLabels},
     logging::controller::ReconcilerError,
     memory::{BinaryMultiple, MemoryQuantity},
-    product_config_utils::{transform_all_roles_to_config, validate_all_roles_and_groups_config},
+    product_config_utils::{
+        transform_all_roles_to_config, validate_all_roles_and_groups_config,
+        ValidatedRoleConfigByPropertyKind,
+    },
     product_logging::{
         self,
         framework::{
@@ -52,27 +68,60 @@ use stackable_operator::{
     },
     role_utils::{GenericRoleConfig, RoleGroupRef},
     status::condition::{
-        compute_conditions, operations::ClusterOperationsConditionBuilder,
-        statefulset::StatefulSetConditionBuilder,
+        compute_conditions, deployment::DeploymentConditionBuilder,
+        operations::ClusterOperationsConditionBuilder, statefulset::StatefulSetConditionBuilder,
     },
     utils::COMMON_BASH_TRAP_FUNCTIONS,
 };
-use strum::EnumDiscriminants;
+use strum::{EnumDiscriminants, IntoEnumIterator};
 use tracing::warn;
 
+use crate::authentication::{resolve_authentication_class, ResolvedAuthenticationClass};
+use crate::autoscaling::{
+    apply_horizontal_pod_autoscalers, build_rolegroup_horizontal_pod_autoscaler,
+    validate_autoscaling,
+};
 use crate::crd::{
-    Container, HelloCluster, HelloClusterStatus, HelloConfig, HelloRole, APPLICATION_PROPERTIES,
-    APP_NAME, HTTP_PORT, HTTP_PORT_NAME, JVM_SECURITY_PROPERTIES, STACKABLE_CONFIG_DIR,
-    STACKABLE_CONFIG_DIR_NAME, STACKABLE_LOG_CONFIG_MOUNT_DIR, STACKABLE_LOG_CONFIG_MOUNT_DIR_NAME,
-    STACKABLE_LOG_DIR, STACKABLE_LOG_DIR_NAME,
+    v1alpha2::SCALE_ROLE_GROUP, Container, GreetingConfig, HelloCluster, HelloClusterStatus,
+    HelloConfig, HelloRole, HelloRoleGroupStatus, HelloWorkload, ProbeConfig, RollingUpdateConfig,
+    APPLICATION_PROPERTIES, APP_NAME, GREETING_COLOR, GREETING_RECIPIENT, JVM_SECURITY_PROPERTIES,
+    LIVENESS_PATH, METRICS_PATH, METRICS_PORT, METRICS_PORT_NAME, OPA_URL, READINESS_PATH,
+    STACKABLE_CONFIG_DIR, STACKABLE_CONFIG_DIR_NAME, STACKABLE_LOG_CONFIG_MOUNT_DIR,
+    STACKABLE_LOG_CONFIG_MOUNT_DIR_NAME, STACKABLE_LOG_DIR, STACKABLE_LOG_DIR_NAME,
+    STACKABLE_SERVER_TLS_DIR, STACKABLE_SERVER_TLS_DIR_NAME, STACKABLE_TLS_STORE_PASSWORD,
+};
+use crate::degraded::DegradedConditionBuilder;
+use crate::discovery::{build_discovery_configmap, role_service_url};
+use crate::edge::{
+    build_edge_role_service, build_edge_rolegroup_config_map, build_edge_rolegroup_deployment,
+    build_edge_rolegroup_service, cache_max_size,
+};
+use crate::greeting::validate_greeting;
+use crate::ingress::{apply_ingress, validate_ingress};
+use crate::jvm::construct_jvm_args;
+use crate::listener::{
+    build_group_listener, group_listener_name, listener_addresses, LISTENER_VOLUME_DIR,
+    LISTENER_VOLUME_NAME,
+};
+use crate::operations::{
+    canary,
+    graceful_shutdown::add_graceful_shutdown_config,
+    pdb::add_pdbs,
+    rollout::{self, add_update_strategy, RolloutProgress},
+};
+use crate::product_logging::{
+    custom_log_config_map_fragment, extend_role_group_config_map, resolve_custom_log_config_map,
+    resolve_vector_aggregator_address,
 };
-use crate::operations::{graceful_shutdown::add_graceful_shutdown_config, pdb::add_pdbs};
-use crate::product_logging::{extend_role_group_config_map, resolve_vector_aggregator_address};
 use crate::OPERATOR_NAME;
 
 pub const HELLO_CONTROLLER_NAME: &str = "hellocluster";
 pub const HELLO_FULL_CONTROLLER_NAME: &str = concatcp!(HELLO_CONTROLLER_NAME, '.', OPERATOR_NAME);
 const DOCKER_IMAGE_BASE_NAME: &str = "hello";
+/// Changes whenever the configuration of a role group changes, which restarts its Pods
+pub const CONFIG_HASH_ANNOTATION: &str = "hello-world.stackable.tech/config-hash";
+/// The rule of the OPA package deciding whether a greeting request is allowed
+const OPA_ALLOW_RULE: &str = "allow";
 
 pub const MAX_LOG_FILES_SIZE: MemoryQuantity = MemoryQuantity {
     value: 10.0,
@@ -80,8 +129,8 @@ pub const MAX_LOG_FILES_SIZE: MemoryQuantity = MemoryQuantity {
 };
 
 pub struct Ctx {
-    pub client: stackable_operator::client::Client,
-    pub product_config: ProductConfigManager,
+    pub client: Client,
+    pub product_config: Arc<ProductConfigManager>,
 }
 
 #[derive(Snafu, Debug, EnumDiscriminants)]
@@ -94,15 +143,105 @@ pub enum Error {
     #[snafu(display("object defines no namespace"))]
     ObjectHasNoNamespace,
 
-    #[snafu(display("object defines no hello role"))]
-    NoServerRole,
-
     #[snafu(display("failed to calculate global service name"))]
     GlobalServiceNameNotFound,
 
-    #[snafu(display("failed to apply global Service"))]
+    #[snafu(display("failed to apply the role Service of role {role}"))]
     ApplyRoleService {
         source: stackable_operator::cluster_resources::Error,
+        role: String,
+    },
+
+    #[snafu(display("failed to apply the Ingress or HTTPRoute"))]
+    ApplyIngress { source: crate::ingress::Error },
+
+    #[snafu(display("invalid ingress"))]
+    InvalidIngress { source: crate::ingress::Error },
+
+    #[snafu(display("failed to build the resources of the edge role"))]
+    BuildEdgeResources { source: crate::edge::Error },
+
+    #[snafu(display("failed to build Listener for role {role}"))]
+    BuildGroupListener {
+        source: crate::listener::Error,
+        role: String,
+    },
+
+    #[snafu(display("failed to apply Listener for role {role}"))]
+    ApplyGroupListener {
+        source: stackable_operator::cluster_resources::Error,
+        role: String,
+    },
+
+    #[snafu(display("failed to add listener volume"))]
+    AddListenerVolume { source: builder::pod::Error },
+
+    #[snafu(display("failed to build discovery ConfigMap"))]
+    BuildDiscoveryConfigMap { source: crate::discovery::Error },
+
+    #[snafu(display("failed to apply discovery ConfigMap"))]
+    ApplyDiscoveryConfigMap {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to build the URL of the role Service"))]
+    BuildRoleServiceUrl { source: crate::discovery::Error },
+
+    #[snafu(display("the product config of {rolegroup} is missing"))]
+    MissingProductConfig {
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to build HorizontalPodAutoscaler for {rolegroup}"))]
+    BuildHorizontalPodAutoscaler {
+        source: crate::autoscaling::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to apply the HorizontalPodAutoscalers"))]
+    ApplyHorizontalPodAutoscalers { source: crate::autoscaling::Error },
+
+    #[snafu(display("invalid autoscaling"))]
+    InvalidAutoscaling { source: crate::autoscaling::Error },
+
+    #[snafu(display("invalid config of {rolegroup}"))]
+    InvalidEdgeConfig {
+        source: crate::crd::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("invalid edge cache"))]
+    InvalidEdgeCache { source: crate::edge::Error },
+
+    #[snafu(display("failed to get the current StatefulSet for {rolegroup}"))]
+    GetRoleGroupStatefulSet {
+        source: stackable_operator::client::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to add the update strategy to the StatefulSet for {rolegroup}"))]
+    AddUpdateStrategy {
+        source: crate::operations::rollout::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to take a snapshot of the rolled out spec"))]
+    SnapshotSpec {
+        source: crate::operations::rollout::Error,
+    },
+
+    #[snafu(display("failed to compute the revision of the spec"))]
+    ComputeSpecRevision {
+        source: crate::operations::rollout::Error,
+    },
+
+    #[snafu(display("the canary role group {role_group} does not exist"))]
+    InvalidCanary { role_group: String },
+
+    #[snafu(display("failed to apply Deployment for {rolegroup}"))]
+    ApplyRoleGroupDeployment {
+        source: stackable_operator::cluster_resources::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
     },
 
     #[snafu(display("failed to apply Service for {rolegroup}"))]
@@ -173,11 +312,33 @@ pub enum Error {
         source: stackable_operator::cluster_resources::Error,
     },
 
+    #[snafu(display("failed to resolve the AuthenticationClass"))]
+    ResolveAuthenticationClass {
+        source: crate::authentication::Error,
+    },
+
+    #[snafu(display("failed to build the authentication config for {rolegroup}"))]
+    BuildAuthenticationConfig {
+        source: crate::authentication::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to resolve the custom log ConfigMap for {rolegroup}"))]
+    ResolveCustomLogConfigMap {
+        source: crate::product_logging::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
     #[snafu(display("failed to resolve the Vector aggregator address"))]
     ResolveVectorAggregatorAddress {
         source: crate::product_logging::Error,
     },
 
+    #[snafu(display("failed to resolve the OPA URL"))]
+    ResolveOpaUrl {
+        source: stackable_operator::commons::opa::Error,
+    },
+
     #[snafu(display("failed to add the logging configuration to the ConfigMap [{cm_name}]"))]
     InvalidLoggingConfig {
         source: crate::product_logging::Error,
@@ -208,6 +369,9 @@ pub enum Error {
         rolegroup: String,
     },
 
+    #[snafu(display("failed to construct JVM arguments"))]
+    ConstructJvmArguments { source: crate::jvm::Error },
+
     #[snafu(display("failed to create PodDisruptionBudget"))]
     FailedToCreatePdb {
         source: crate::operations::pdb::Error,
@@ -223,6 +387,11 @@ pub enum Error {
         source: stackable_operator::kvp::LabelError,
     },
 
+    #[snafu(display("failed to build Annotation"))]
+    AnnotationBuild {
+        source: stackable_operator::kvp::AnnotationError,
+    },
+
     #[snafu(display("failed to build Metadata"))]
     MetadataBuild {
         source: stackable_operator::builder::meta::Error,
@@ -240,11 +409,27 @@ pub enum Error {
     #[snafu(display("failed to add needed volume"))]
     AddVolume { source: builder::pod::Error },
 
+    #[snafu(display("failed to build TLS certificate SecretClass Volume"))]
+    TlsCertSecretClassVolumeBuild {
+        source: stackable_operator::builder::pod::volume::SecretOperatorVolumeSourceBuilderError,
+    },
+
     #[snafu(display("failed to add needed volumeMount"))]
     AddVolumeMount {
         source: builder::pod::container::Error,
     },
 
+    #[snafu(display("invalid greeting for {rolegroup}"))]
+    InvalidGreeting {
+        source: crate::greeting::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display(
+        "the httpPort {port} is invalid, it must be at least 1024 and differ from the metrics port {METRICS_PORT}"
+    ))]
+    InvalidHttpPort { port: u16 },
+
     #[snafu(display("HelloCluster object is invalid"))]
     InvalidHelloCluster {
         source: error_boundary::InvalidObject,
@@ -275,38 +460,51 @@ pub async fn reconcile_hello(
         .spec
         .image
         .resolve(DOCKER_IMAGE_BASE_NAME, crate::built_info::PKG_VERSION);
-    let hello_role = HelloRole::Server;
-
-    let validated_config = validate_all_roles_and_groups_config(
+    let server_role = HelloRole::Server;
+
+    validate_http_port(hello)?;
+    validate_canary(hello)?;
+    validate_ingress(hello).context(InvalidIngressSnafu)?;
+    validate_autoscalings(hello)?;
+    validate_edges(hello)?;
+    let validated_config = validated_product_config(
+        hello,
         &resolved_product_image.product_version,
-        &transform_all_roles_to_config(
-            hello,
-            [(
-                HelloRole::Server.to_string(),
-                (
-                    vec![
-                        PropertyNameKind::Env,
-                        PropertyNameKind::Cli,
-                        PropertyNameKind::File(APPLICATION_PROPERTIES.to_string()),
-                        PropertyNameKind::File(JVM_SECURITY_PROPERTIES.to_string()),
-                    ],
-                    hello.spec.servers.clone().context(NoServerRoleSnafu)?,
-                ),
-            )]
-            .into(),
-        )
-        .context(GenerateProductConfigSnafu)?,
         &ctx.product_config,
-        false,
-        false,
-    )
-    .context(InvalidProductConfigSnafu)?;
+    )?;
 
     let server_config = validated_config
         .get(&HelloRole::Server.to_string())
         .map(Cow::Borrowed)
         .unwrap_or_default();
 
+    // The greeting ends up in the HTML of the greeting page, so it is checked before anything is
+    // applied. Invalid greetings are reported in the status, as they can only be fixed by the user.
+    if let Err(error) = validate_greetings(hello, &server_config) {
+        if let Error::InvalidGreeting { source, rolegroup } = &error {
+            let degraded_cond_builder = DegradedConditionBuilder {
+                invalid_greeting: Some(format!("invalid greeting for {rolegroup}: {source}")),
+                // Nothing is rolled out, so a failed rollout stays as it is
+                rollout_failed: rollout::rollout_failure(
+                    hello,
+                    hello
+                        .status
+                        .as_ref()
+                        .and_then(|status| status.rollout.as_ref()),
+                ),
+            };
+            let status = HelloClusterStatus {
+                conditions: compute_conditions(hello, &[&degraded_cond_builder]),
+                ..hello.status.clone().unwrap_or_default()
+            };
+            client
+                .apply_patch_status(OPERATOR_NAME, hello, &status)
+                .await
+                .context(ApplyStatusSnafu)?;
+        }
+        return Err(error);
+    }
+
     let mut cluster_resources = ClusterResources::new(
         APP_NAME,
         OPERATOR_NAME,
@@ -334,87 +532,293 @@ pub async fn reconcile_hello(
         .await
         .context(ApplyRoleBindingSnafu)?;
 
-    let server_role_service = build_server_role_service(hello, &resolved_product_image)?;
+    apply_ingress(
+        client,
+        hello,
+        &resolved_product_image,
+        &cluster_resources
+            .get_required_labels()
+            .context(GetRequiredLabelsSnafu)?,
+    )
+    .await
+    .context(ApplyIngressSnafu)?;
 
-    // we have to get the assigned ports
-    cluster_resources
-        .add(client, server_role_service)
+    let server_listener = build_group_listener(
+        hello,
+        &resolved_product_image,
+        &server_role,
+        &hello.spec.cluster_config.listener_class,
+    )
+    .with_context(|_| BuildGroupListenerSnafu {
+        role: server_role.to_string(),
+    })?;
+    let server_listener = cluster_resources
+        .add(client, server_listener)
         .await
-        .context(ApplyRoleServiceSnafu)?;
+        .with_context(|_| ApplyGroupListenerSnafu {
+            role: server_role.to_string(),
+        })?;
 
-    let vector_aggregator_address = resolve_vector_aggregator_address(hello, client)
+    let discovery_cm = build_discovery_configmap(
+        hello,
+        &resolved_product_image,
+        &client.kubernetes_cluster_info,
+    )
+    .context(BuildDiscoveryConfigMapSnafu)?;
+    cluster_resources
+        .add(client, discovery_cm)
         .await
-        .context(ResolveVectorAggregatorAddressSnafu)?;
+        .context(ApplyDiscoveryConfigMapSnafu)?;
 
-    let mut ss_cond_builder = StatefulSetConditionBuilder::default();
+    let references = resolve_references(hello, client).await?;
 
-    for (rolegroup_name, rolegroup_config) in server_config.iter() {
-        let role_group_ref = hello.server_rolegroup_ref(rolegroup_name);
+    // While a change of the spec is rolled out to the canary role group, the other role groups
+    // are rendered from the last known good spec. After a failed rollout, all of them are.
+    // The resources shared by all role groups, e.g. the role Services, follow the current spec.
+    let spec_revision = rollout::spec_revision(&hello.spec).context(ComputeSpecRevisionSnafu)?;
+    let rollout_failed = rollout::has_failed(hello, &spec_revision);
+    let held_back = match rollout::pending_last_known_good(hello, &spec_revision)
+        .filter(|_| rollout_failed || canary::holds_back(hello, &spec_revision))
+    {
+        Some(previous) => {
+            let previous_product_image = previous
+                .spec
+                .image
+                .resolve(DOCKER_IMAGE_BASE_NAME, crate::built_info::PKG_VERSION);
+            let previous_config = validated_product_config(
+                &previous,
+                &previous_product_image.product_version,
+                &ctx.product_config,
+            )?;
+            let previous_references = resolve_references(&previous, client).await?;
+            Some(HeldBackSpec {
+                hello: previous,
+                resolved_product_image: previous_product_image,
+                config: previous_config,
+                references: previous_references,
+            })
+        }
+        None => None,
+    };
 
-        let config = hello
-            .merged_config(&HelloRole::Server, &role_group_ref)
-            .context(FailedToResolveResourceConfigSnafu)?;
+    let sa_name = rbac_sa.name_any();
+    let role_context = RoleContext {
+        hello,
+        client,
+        resolved_product_image: &resolved_product_image,
+        server_config: &server_config,
+        references: &references,
+        held_back: held_back.as_ref(),
+        rollout_failed,
+        sa_name: &sa_name,
+    };
 
-        let rg_service = build_rolegroup_service(hello, &resolved_product_image, &role_group_ref)?;
-        let rg_configmap = build_server_rolegroup_config_map(
-            hello,
-            &resolved_product_image,
-            &role_group_ref,
-            rolegroup_config,
-            &config,
-            vector_aggregator_address.as_deref(),
-        )?;
-        let rg_statefulset = build_server_rolegroup_statefulset(
-            hello,
-            &resolved_product_image,
-            &hello_role,
-            &role_group_ref,
-            rolegroup_config,
-            &config,
-            &rbac_sa.name_any(),
-        )?;
+    let mut ss_cond_builder = StatefulSetConditionBuilder::default();
+    let mut deployment_cond_builder = DeploymentConditionBuilder::default();
+    let mut role_group_statuses = Vec::new();
+    let mut scale_status = None;
+    let mut canary_rolled_out = false;
+    let mut current_progress = RolloutProgress {
+        rolled_out: true,
+        ..RolloutProgress::default()
+    };
+    let mut all_rolled_out = true;
+    let mut autoscalers = Vec::new();
 
-        cluster_resources
-            .add(client, rg_service)
-            .await
-            .context(ApplyRoleGroupServiceSnafu {
-                rolegroup: role_group_ref.clone(),
-            })?;
+    for role in HelloRole::iter() {
+        let Some(any_role) = hello.role(&role) else {
+            continue;
+        };
 
+        let role_service = role_context.build_role_service(&role)?;
         cluster_resources
-            .add(client, rg_configmap)
+            .add(client, role_service)
             .await
-            .context(ApplyRoleGroupConfigSnafu {
-                rolegroup: role_group_ref.clone(),
+            .with_context(|_| ApplyRoleServiceSnafu {
+                role: role.to_string(),
             })?;
 
-        ss_cond_builder.add(
+        let role_groups = any_role.role_groups();
+        for rolegroup_name in role_groups.keys() {
+            let RoleGroupResources {
+                role_group_ref,
+                service,
+                config_map,
+                workload,
+                autoscaler,
+                rolling_update,
+                renders_current_spec,
+            } = role_context.build_role_group(&role, rolegroup_name).await?;
+
             cluster_resources
-                .add(client, rg_statefulset)
+                .add(client, service)
                 .await
-                .context(ApplyRoleGroupStatefulSetSnafu {
+                .context(ApplyRoleGroupServiceSnafu {
                     rolegroup: role_group_ref.clone(),
-                })?,
-        );
-    }
+                })?;
+            cluster_resources
+                .add(client, config_map)
+                .await
+                .context(ApplyRoleGroupConfigSnafu {
+                    rolegroup: role_group_ref.clone(),
+                })?;
+            autoscalers.extend(autoscaler);
+
+            let rg_workload = match workload {
+                RoleGroupWorkload::StatefulSet(mut rg_statefulset) => {
+                    if let Some(rolling_update) = &rolling_update {
+                        // The next stage of a staged rollout depends on the progress of the
+                        // current one
+                        let current_statefulset = if rolling_update.staged {
+                            client
+                                .get_opt::<StatefulSet>(
+                                    &rg_statefulset.name_any(),
+                                    hello
+                                        .namespace()
+                                        .as_deref()
+                                        .context(ObjectHasNoNamespaceSnafu)?,
+                                )
+                                .await
+                                .context(GetRoleGroupStatefulSetSnafu {
+                                    rolegroup: role_group_ref.clone(),
+                                })?
+                        } else {
+                            None
+                        };
+                        add_update_strategy(
+                            &mut rg_statefulset,
+                            current_statefulset.as_ref(),
+                            rolling_update,
+                        )
+                        .context(AddUpdateStrategySnafu {
+                            rolegroup: role_group_ref.clone(),
+                        })?;
+                    }
+
+                    let rg_statefulset = cluster_resources
+                        .add(client, rg_statefulset)
+                        .await
+                        .context(ApplyRoleGroupStatefulSetSnafu {
+                            rolegroup: role_group_ref.clone(),
+                        })?;
+                    ss_cond_builder.add(rg_statefulset.clone());
+                    RoleGroupWorkload::StatefulSet(rg_statefulset)
+                }
+                RoleGroupWorkload::Deployment(rg_deployment) => {
+                    let rg_deployment = cluster_resources
+                        .add(client, rg_deployment)
+                        .await
+                        .context(ApplyRoleGroupDeploymentSnafu {
+                            rolegroup: role_group_ref.clone(),
+                        })?;
+                    deployment_cond_builder.add(rg_deployment.clone());
+                    RoleGroupWorkload::Deployment(rg_deployment)
+                }
+            };
+            let rg_status = role_group_status(&role_group_ref, &rg_workload);
+
+            // The reverse proxies always follow the current spec, they take no part in canary
+            // rollouts and rollbacks
+            if role == HelloRole::Server {
+                let rg_rolled_out = is_rolled_out(&rg_workload);
+                all_rolled_out &= rg_rolled_out;
+                if renders_current_spec {
+                    current_progress.updated_replicas += rg_status.updated_replicas;
+                    current_progress.ready_replicas += rg_status.ready_replicas;
+                    current_progress.rolled_out &= rg_rolled_out;
+                }
+                if canary::is_canary(hello, rolegroup_name) {
+                    canary_rolled_out = rg_rolled_out;
+                }
+                if role_groups.len() == 1 && rolegroup_name.as_str() == SCALE_ROLE_GROUP {
+                    scale_status = Some(scale_status_of(&rg_workload));
+                }
+            }
+            role_group_statuses.push(rg_status);
+        }
 
-    let role_config = hello.role_config(&hello_role);
-    if let Some(GenericRoleConfig {
-        pod_disruption_budget: pdb,
-    }) = role_config
-    {
-        add_pdbs(pdb, hello, &hello_role, client, &mut cluster_resources)
+        let GenericRoleConfig {
+            pod_disruption_budget: pdb,
+        } = any_role.role_config();
+        add_pdbs(pdb, hello, &role, client, &mut cluster_resources)
             .await
             .context(FailedToCreatePdbSnafu)?;
     }
 
+    apply_horizontal_pod_autoscalers(
+        client,
+        hello,
+        &cluster_resources
+            .get_required_labels()
+            .context(GetRequiredLabelsSnafu)?,
+        autoscalers,
+    )
+    .await
+    .context(ApplyHorizontalPodAutoscalersSnafu)?;
+
     let cluster_operation_cond_builder =
         ClusterOperationsConditionBuilder::new(&hello.spec.cluster_operation);
 
+    let now = rollout::now();
+    let (canary_status, canary_requeue) =
+        canary::canary_status(hello, &spec_revision, canary_rolled_out, &now);
+    // Paused clusters are not rolled out, so the progress deadline does not run out meanwhile
+    let (rollout_status, rollout_requeue) = rollout::rollout_status(
+        hello,
+        &spec_revision,
+        RolloutProgress {
+            rolled_out: current_progress.rolled_out
+                || hello.spec.cluster_operation.reconciliation_paused,
+            ..current_progress
+        },
+        &now,
+    );
+    let degraded_cond_builder = DegradedConditionBuilder {
+        invalid_greeting: None,
+        rollout_failed: rollout::rollout_failure(hello, rollout_status.as_ref()),
+    };
+    // Paused clusters are not rolled out and stopped clusters run no Pods, which could prove the
+    // spec to be good, although their role groups count as rolled out
+    let last_known_good = if held_back.is_none()
+        && all_rolled_out
+        && !hello.spec.cluster_operation.reconciliation_paused
+        && !hello.spec.cluster_operation.stopped
+    {
+        rollout::snapshot(hello, &spec_revision).context(SnapshotSpecSnafu)?
+    } else {
+        hello
+            .status
+            .as_ref()
+            .and_then(|status| status.last_known_good.clone())
+    };
+
     let status = HelloClusterStatus {
-        conditions: compute_conditions(hello, &[&ss_cond_builder, &cluster_operation_cond_builder]),
+        conditions: compute_conditions(
+            hello,
+            &[
+                &ss_cond_builder,
+                &deployment_cond_builder,
+                &cluster_operation_cond_builder,
+                &degraded_cond_builder,
+            ],
+        ),
+        observed_generation: hello.metadata.generation,
+        product_version: Some(resolved_product_image.product_version.clone()),
+        endpoint: Some(
+            role_service_url(hello, &client.kubernetes_cluster_info)
+                .context(BuildRoleServiceUrlSnafu)?,
+        ),
+        listener_addresses: listener_addresses(&server_role, &server_listener),
+        role_groups: role_group_statuses,
+        replicas: scale_status.as_ref().map(|(replicas, _)| *replicas),
+        selector: scale_status.map(|(_, selector)| selector),
+        last_known_good,
+        canary: canary_status,
+        rollout: rollout_status,
     };
 
+    // Failed reconciliations return early and leave the status as it is, see
+    // `HelloClusterStatus::observed_generation`
     client
         .apply_patch_status(OPERATOR_NAME, hello, &status)
         .await
@@ -425,9 +829,391 @@ pub async fn reconcile_hello(
         .await
         .context(DeleteOrphanedResourcesSnafu)?;
 
-    Ok(Action::await_change())
+    Ok([canary_requeue, rollout_requeue]
+        .into_iter()
+        .flatten()
+        .min()
+        .map_or_else(Action::await_change, Action::requeue))
+}
+
+/// The external resources referenced by a spec, which are needed to render its role groups
+struct ResolvedReferences {
+    vector_aggregator_address: Option<String>,
+    opa_url: Option<String>,
+    authentication_class: Option<ResolvedAuthenticationClass>,
+}
+
+async fn resolve_references(hello: &HelloCluster, client: &Client) -> Result<ResolvedReferences> {
+    let vector_aggregator_address = resolve_vector_aggregator_address(hello, client)
+        .await
+        .context(ResolveVectorAggregatorAddressSnafu)?;
+
+    let opa_url = match hello.opa_config() {
+        Some(opa_config) => Some(
+            opa_config
+                .full_document_url_from_config_map(
+                    client,
+                    hello,
+                    Some(OPA_ALLOW_RULE),
+                    OpaApiVersion::V1,
+                )
+                .await
+                .context(ResolveOpaUrlSnafu)?,
+        ),
+        None => None,
+    };
+
+    let authentication_class = resolve_authentication_class(hello, client)
+        .await
+        .context(ResolveAuthenticationClassSnafu)?;
+
+    Ok(ResolvedReferences {
+        vector_aggregator_address,
+        opa_url,
+        authentication_class,
+    })
+}
+
+/// The last known good spec, which role groups are rendered from while the current spec is held
+/// back
+struct HeldBackSpec {
+    hello: HelloCluster,
+    resolved_product_image: ResolvedProductImage,
+    config: ValidatedRoleConfigByPropertyKind,
+    references: ResolvedReferences,
+}
+
+/// The resources of a role group, which are applied the same way for all roles
+struct RoleGroupResources {
+    role_group_ref: RoleGroupRef<HelloCluster>,
+    service: Service,
+    config_map: ConfigMap,
+    workload: RoleGroupWorkload,
+    autoscaler: Option<HorizontalPodAutoscaler>,
+    /// The rolling update of a [`StatefulSet`], which depends on the applied [`StatefulSet`]
+    rolling_update: Option<RollingUpdateConfig>,
+    /// Whether the role group is rendered from the current spec rather than the held back one
+    renders_current_spec: bool,
+}
+
+/// Everything the resources of the roles are built from
+struct RoleContext<'a> {
+    hello: &'a HelloCluster,
+    client: &'a Client,
+    resolved_product_image: &'a ResolvedProductImage,
+    server_config: &'a HashMap<String, HashMap<PropertyNameKind, BTreeMap<String, String>>>,
+    references: &'a ResolvedReferences,
+    held_back: Option<&'a HeldBackSpec>,
+    rollout_failed: bool,
+    sa_name: &'a str,
 }
 
+impl RoleContext<'_> {
+    /// The role [`Service`] load-balancing over all Pods of the role
+    fn build_role_service(&self, role: &HelloRole) -> Result<Service> {
+        match role {
+            HelloRole::Server => build_server_role_service(self.hello, self.resolved_product_image),
+            HelloRole::Edge => build_edge_role_service(self.hello, self.resolved_product_image)
+                .context(BuildEdgeResourcesSnafu),
+        }
+    }
+
+    async fn build_role_group(
+        &self,
+        role: &HelloRole,
+        rolegroup_name: &str,
+    ) -> Result<RoleGroupResources> {
+        match role {
+            HelloRole::Server => self.build_server_role_group(rolegroup_name).await,
+            HelloRole::Edge => self.build_edge_role_group(rolegroup_name),
+        }
+    }
+
+    /// While a change of the spec is held back, all server role groups but the canary role group
+    /// are rendered from the last known good spec. After a failed rollout, all of them are.
+    async fn build_server_role_group(&self, rolegroup_name: &str) -> Result<RoleGroupResources> {
+        let rolegroup_config =
+            self.server_config
+                .get(rolegroup_name)
+                .with_context(|| MissingProductConfigSnafu {
+                    rolegroup: self.hello.server_rolegroup_ref(rolegroup_name),
+                })?;
+        let is_canary = canary::is_canary(self.hello, rolegroup_name);
+        let previous = self
+            .held_back
+            .filter(|_| self.rollout_failed || !is_canary)
+            .and_then(|held_back| {
+                let previous_rolegroup_config = held_back
+                    .config
+                    .get(&HelloRole::Server.to_string())?
+                    .get(rolegroup_name)?;
+                Some((held_back, previous_rolegroup_config))
+            });
+        let renders_current_spec = previous.is_none();
+        let (hello, resolved_product_image, rolegroup_config, references) = match previous {
+            Some((held_back, previous_rolegroup_config)) => (
+                &held_back.hello,
+                &held_back.resolved_product_image,
+                previous_rolegroup_config,
+                &held_back.references,
+            ),
+            None => (
+                self.hello,
+                self.resolved_product_image,
+                rolegroup_config,
+                self.references,
+            ),
+        };
+        let role_group_ref = hello.server_rolegroup_ref(rolegroup_name);
+
+        let config = hello
+            .merged_config(&HelloRole::Server, &role_group_ref)
+            .context(FailedToResolveResourceConfigSnafu)?;
+
+        let service = build_rolegroup_service(hello, resolved_product_image, &role_group_ref)?;
+        let config_map = build_server_rolegroup_config_map(
+            hello,
+            resolved_product_image,
+            &role_group_ref,
+            rolegroup_config,
+            &config,
+            references.vector_aggregator_address.as_deref(),
+            references.authentication_class.as_ref(),
+            references.opa_url.as_deref(),
+        )?;
+        let custom_log_config_map =
+            resolve_custom_log_config_map(hello, self.client, &config.logging, &Container::Hello)
+                .await
+                .context(ResolveCustomLogConfigMapSnafu {
+                    rolegroup: role_group_ref.clone(),
+                })?;
+        let config_hash = config_hash(iter::once(&config_map).chain(&custom_log_config_map));
+        let workload = build_server_rolegroup_workload(
+            hello,
+            resolved_product_image,
+            &HelloRole::Server,
+            &role_group_ref,
+            rolegroup_config,
+            &config,
+            references.authentication_class.as_ref(),
+            self.sa_name,
+            &config_hash,
+        )?;
+        let autoscaler = config
+            .autoscaling
+            .as_ref()
+            .map(|autoscaling| {
+                build_rolegroup_horizontal_pod_autoscaler(
+                    hello,
+                    resolved_product_image,
+                    &role_group_ref,
+                    &config.workload,
+                    autoscaling,
+                )
+                .context(BuildHorizontalPodAutoscalerSnafu {
+                    rolegroup: role_group_ref.clone(),
+                })
+            })
+            .transpose()?;
+
+        Ok(RoleGroupResources {
+            role_group_ref,
+            service,
+            config_map,
+            workload,
+            autoscaler,
+            rolling_update: Some(config.rolling_update),
+            renders_current_spec,
+        })
+    }
+
+    /// The reverse proxies always follow the current spec
+    fn build_edge_role_group(&self, rolegroup_name: &str) -> Result<RoleGroupResources> {
+        let hello = self.hello;
+        let role_group_ref = HelloRole::Edge.rolegroup_ref(hello, rolegroup_name);
+
+        let config = hello
+            .merged_edge_config(&role_group_ref)
+            .context(FailedToResolveResourceConfigSnafu)?;
+
+        let service =
+            build_edge_rolegroup_service(hello, self.resolved_product_image, &role_group_ref)
+                .context(BuildEdgeResourcesSnafu)?;
+        let config_map = build_edge_rolegroup_config_map(
+            hello,
+            self.resolved_product_image,
+            &role_group_ref,
+            &config,
+            &self.client.kubernetes_cluster_info,
+        )
+        .context(BuildEdgeResourcesSnafu)?;
+        let deployment = build_edge_rolegroup_deployment(
+            hello,
+            self.resolved_product_image,
+            &role_group_ref,
+            &config,
+            &config_hash(iter::once(&config_map)),
+            self.sa_name,
+        )
+        .context(BuildEdgeResourcesSnafu)?;
+
+        Ok(RoleGroupResources {
+            role_group_ref,
+            service,
+            config_map,
+            workload: RoleGroupWorkload::Deployment(deployment),
+            autoscaler: None,
+            rolling_update: None,
+            renders_current_spec: true,
+        })
+    }
+}
+
+/// Runs all checks which only depend on the [`HelloCluster`] itself, so that invalid objects can
+/// already be rejected at admission time, see [`crate::webhook::validation`].
+pub fn validate_hello_cluster(
+    hello: &HelloCluster,
+    product_config: &ProductConfigManager,
+) -> Result<()> {
+    let resolved_product_image = hello
+        .spec
+        .image
+        .resolve(DOCKER_IMAGE_BASE_NAME, crate::built_info::PKG_VERSION);
+
+    validate_http_port(hello)?;
+    validate_canary(hello)?;
+    validate_ingress(hello).context(InvalidIngressSnafu)?;
+    validate_autoscalings(hello)?;
+    validate_edges(hello)?;
+    let validated_config = validated_product_config(
+        hello,
+        &resolved_product_image.product_version,
+        product_config,
+    )?;
+    let server_config = validated_config
+        .get(&HelloRole::Server.to_string())
+        .cloned()
+        .unwrap_or_default();
+
+    validate_greetings(hello, &server_config)
+}
+
+/// The greeting and the metrics are served on different ports, so they must not be the same.
+/// The containers do not run as root, so they cannot bind privileged ports.
+fn validate_http_port(hello: &HelloCluster) -> Result<()> {
+    let port = hello.http_port();
+    ensure!(
+        port >= 1024 && port != METRICS_PORT,
+        InvalidHttpPortSnafu { port }
+    );
+    Ok(())
+}
+
+/// The `autoscaling` of every server role group must be within bounds
+fn validate_autoscalings(hello: &HelloCluster) -> Result<()> {
+    for rolegroup_name in hello.spec.servers.role_groups.keys() {
+        let role_group_ref = hello.server_rolegroup_ref(rolegroup_name);
+        let config = hello
+            .merged_config(&HelloRole::Server, &role_group_ref)
+            .context(FailedToResolveResourceConfigSnafu)?;
+        if let Some(autoscaling) = &config.autoscaling {
+            validate_autoscaling(&role_group_ref, autoscaling).context(InvalidAutoscalingSnafu)?;
+        }
+    }
+    Ok(())
+}
+
+/// The config of every edge role group must be complete and its `cache.maxSize` must be valid
+fn validate_edges(hello: &HelloCluster) -> Result<()> {
+    let Some(edges) = &hello.spec.edges else {
+        return Ok(());
+    };
+    for rolegroup_name in edges.role.role_groups.keys() {
+        let role_group_ref = HelloRole::Edge.rolegroup_ref(hello, rolegroup_name);
+        let config = hello
+            .merged_edge_config(&role_group_ref)
+            .with_context(|_| InvalidEdgeConfigSnafu {
+                rolegroup: role_group_ref.clone(),
+            })?;
+        cache_max_size(&role_group_ref, &config).context(InvalidEdgeCacheSnafu)?;
+    }
+    Ok(())
+}
+
+/// The canary role group must be one of the server role groups
+fn validate_canary(hello: &HelloCluster) -> Result<()> {
+    if let Some(canary) = &hello.spec.cluster_config.canary {
+        ensure!(
+            hello
+                .spec
+                .servers
+                .role_groups
+                .contains_key(&canary.role_group),
+            InvalidCanarySnafu {
+                role_group: &canary.role_group
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Transforms the roles into the product config and validates it, including the configOverrides
+fn validated_product_config(
+    hello: &HelloCluster,
+    product_version: &str,
+    product_config: &ProductConfigManager,
+) -> Result<ValidatedRoleConfigByPropertyKind> {
+    validate_all_roles_and_groups_config(
+        product_version,
+        &transform_all_roles_to_config(
+            hello,
+            [(
+                HelloRole::Server.to_string(),
+                (
+                    vec![
+                        PropertyNameKind::Env,
+                        PropertyNameKind::Cli,
+                        PropertyNameKind::File(APPLICATION_PROPERTIES.to_string()),
+                        PropertyNameKind::File(JVM_SECURITY_PROPERTIES.to_string()),
+                    ],
+                    hello.spec.servers.clone(),
+                ),
+            )]
+            .into(),
+        )
+        .context(GenerateProductConfigSnafu)?,
+        product_config,
+        false,
+        false,
+    )
+    .context(InvalidProductConfigSnafu)
+}
+
+/// Checks that the merged config of every server role group is valid and that its greeting can
+/// be safely embedded into the greeting page
+fn validate_greetings(
+    hello: &HelloCluster,
+    server_config: &HashMap<String, HashMap<PropertyNameKind, BTreeMap<String, String>>>,
+) -> Result<()> {
+    for (rolegroup_name, rolegroup_config) in server_config {
+        let role_group_ref = hello.server_rolegroup_ref(rolegroup_name);
+        let config = hello
+            .merged_config(&HelloRole::Server, &role_group_ref)
+            .context(FailedToResolveResourceConfigSnafu)?;
+
+        validate_greeting(&effective_greeting(&config, rolegroup_config)).context(
+            InvalidGreetingSnafu {
+                rolegroup: role_group_ref,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The role [`Service`] load-balances over all Pods of the role and is only reachable from within
+/// the Kubernetes cluster. External access is provided by the role
+/// [`Listener`](stackable_operator::commons::listener::Listener).
 pub fn build_server_role_service(
     hello: &HelloCluster,
     resolved_product_image: &ResolvedProductImage,
@@ -452,8 +1238,8 @@ pub fn build_server_role_service(
             .context(MetadataBuildSnafu)?
             .build(),
         spec: Some(ServiceSpec {
-            type_: Some(hello.spec.cluster_config.listener_class.k8s_service_type()),
-            ports: Some(service_ports()),
+            type_: Some("ClusterIP".to_string()),
+            ports: Some(service_ports(hello)),
             selector: Some(
                 Labels::role_selector(hello, APP_NAME, &role_name)
                     .context(LabelBuildSnafu)?
@@ -465,6 +1251,27 @@ pub fn build_server_role_service(
     })
 }
 
+/// The greeting as it is written to the `application.properties`, where the configOverrides take
+/// precedence over the merged config
+fn effective_greeting(
+    merged_config: &HelloConfig,
+    rolegroup_config: &HashMap<PropertyNameKind, BTreeMap<String, String>>,
+) -> GreetingConfig {
+    let overrides =
+        rolegroup_config.get(&PropertyNameKind::File(APPLICATION_PROPERTIES.to_string()));
+    let effective = |property: &str, merged: &String| {
+        overrides
+            .and_then(|overrides| overrides.get(property))
+            .unwrap_or(merged)
+            .clone()
+    };
+
+    GreetingConfig {
+        recipient: effective(GREETING_RECIPIENT, &merged_config.greeting.recipient),
+        color: effective(GREETING_COLOR, &merged_config.greeting.color),
+    }
+}
+
 /// The rolegroup [`ConfigMap`] configures the rolegroup based on the configuration given by the administrator
 fn build_server_rolegroup_config_map(
     hello: &HelloCluster,
@@ -473,24 +1280,46 @@ fn build_server_rolegroup_config_map(
     role_group_config: &HashMap<PropertyNameKind, BTreeMap<String, String>>,
     merged_config: &HelloConfig,
     vector_aggregator_address: Option<&str>,
+    authentication_class: Option<&ResolvedAuthenticationClass>,
+    opa_url: Option<&str>,
 ) -> Result<ConfigMap> {
-    let mut application_properties = String::new();
-
-    for (property_name_kind, config) in role_group_config {
-        match property_name_kind {
-            PropertyNameKind::File(file_name) if file_name == APPLICATION_PROPERTIES => {
-                let transformed_config: BTreeMap<String, Option<String>> = config
-                    .iter()
-                    .map(|(k, v)| (k.clone(), Some(v.clone())))
-                    .collect();
-
-                application_properties = to_java_properties_string(transformed_config.iter())
-                    .context(PropertiesWriteSnafu)?;
-            }
-            _ => {}
-        }
+    let mut application_properties: BTreeMap<String, Option<String>> = BTreeMap::new();
+
+    if let Some(authentication_class) = authentication_class {
+        application_properties.extend(
+            authentication_class
+                .application_properties()
+                .with_context(|_| BuildAuthenticationConfigSnafu {
+                    rolegroup: rolegroup.clone(),
+                })?
+                .into_iter()
+                .map(|(k, v)| (k, Some(v))),
+        );
+    }
+
+    application_properties.insert(
+        GREETING_RECIPIENT.to_owned(),
+        Some(merged_config.greeting.recipient.clone()),
+    );
+    application_properties.insert(
+        GREETING_COLOR.to_owned(),
+        Some(merged_config.greeting.color.clone()),
+    );
+
+    if let Some(opa_url) = opa_url {
+        application_properties.insert(OPA_URL.to_owned(), Some(opa_url.to_owned()));
     }
 
+    // The properties from the product config, including the configOverrides, take precedence
+    application_properties.extend(
+        role_group_config
+            .get(&PropertyNameKind::File(APPLICATION_PROPERTIES.to_string()))
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, v)| (k, Some(v))),
+    );
+
     // build JVM security properties from configOverrides.
     let jvm_sec_props: BTreeMap<String, Option<String>> = role_group_config
         .get(&PropertyNameKind::File(JVM_SECURITY_PROPERTIES.to_string()))
@@ -518,7 +1347,11 @@ fn build_server_rolegroup_config_map(
                 .context(MetadataBuildSnafu)?
                 .build(),
         )
-        .add_data(APPLICATION_PROPERTIES, application_properties)
+        .add_data(
+            APPLICATION_PROPERTIES,
+            to_java_properties_string(application_properties.iter())
+                .context(PropertiesWriteSnafu)?,
+        )
         .add_data(
             JVM_SECURITY_PROPERTIES,
             to_java_properties_string(jvm_sec_props.iter()).with_context(|_| {
@@ -566,12 +1399,25 @@ fn build_rolegroup_service(
                 &rolegroup.role_group,
             ))
             .context(MetadataBuildSnafu)?
+            .with_label(Label::try_from(("prometheus.io/scrape", "true")).context(LabelBuildSnafu)?)
+            .with_annotation(
+                Annotation::try_from(("prometheus.io/path", METRICS_PATH))
+                    .context(AnnotationBuildSnafu)?,
+            )
+            .with_annotation(
+                Annotation::try_from(("prometheus.io/port", METRICS_PORT.to_string().as_str()))
+                    .context(AnnotationBuildSnafu)?,
+            )
+            .with_annotation(
+                Annotation::try_from(("prometheus.io/scheme", "http"))
+                    .context(AnnotationBuildSnafu)?,
+            )
             .build(),
         spec: Some(ServiceSpec {
             // Internal communication does not need to be exposed
             type_: Some("ClusterIP".to_string()),
             cluster_ip: Some("None".to_string()),
-            ports: Some(service_ports()),
+            ports: Some(rolegroup_service_ports(hello)),
             selector: Some(
                 Labels::role_group_selector(
                     hello,
@@ -589,23 +1435,30 @@ fn build_rolegroup_service(
     })
 }
 
-/// The rolegroup [`StatefulSet`] runs the rolegroup, as configured by the administrator.
+/// The workload running the Pods of a role group
+enum RoleGroupWorkload {
+    StatefulSet(StatefulSet),
+    Deployment(Deployment),
+}
+
+/// The rolegroup [`StatefulSet`] or [`Deployment`] runs the rolegroup, as configured by the
+/// administrator.
 ///
 /// The [`Pod`](`stackable_operator::k8s_openapi::api::core::v1::Pod`)s are accessible through the
 /// corresponding [`Service`] (from [`build_rolegroup_service`]).
-fn build_server_rolegroup_statefulset(
+fn build_server_rolegroup_workload(
     hello: &HelloCluster,
     resolved_product_image: &ResolvedProductImage,
     hello_role: &HelloRole,
     role_group_ref: &RoleGroupRef<HelloCluster>,
     rolegroup_config: &HashMap<PropertyNameKind, BTreeMap<String, String>>,
     merged_config: &HelloConfig,
+    authentication_class: Option<&ResolvedAuthenticationClass>,
     sa_name: &str,
-) -> Result<StatefulSet> {
+    config_hash: &str,
+) -> Result<RoleGroupWorkload> {
     // TODO this function still needs to be checked
-    let role = hello
-        .role(hello_role)
-        .context(InternalOperatorFailureSnafu)?;
+    let role = &hello.spec.servers;
     let role_group = hello
         .role_group(role_group_ref)
         .context(InternalOperatorFailureSnafu)?;
@@ -634,6 +1487,26 @@ fn build_server_rolegroup_statefulset(
         format!("{STACKABLE_LOG_DIR}/containerdebug"),
     );
 
+    let mut pod_builder = PodBuilder::new();
+
+    if hello.server_secret_class().is_some() {
+        container_builder
+            .add_volume_mount(STACKABLE_SERVER_TLS_DIR_NAME, STACKABLE_SERVER_TLS_DIR)
+            .context(AddVolumeMountSnafu)?;
+    }
+
+    if let Some(authentication_class) = authentication_class {
+        authentication_class
+            .add_volumes_and_mounts(&mut pod_builder, &mut container_builder)
+            .with_context(|_| BuildAuthenticationConfigSnafu {
+                rolegroup: role_group_ref.clone(),
+            })?;
+    }
+
+    let jvm_args = construct_jvm_args(hello, hello_role, role_group_ref, merged_config)
+        .context(ConstructJvmArgumentsSnafu)?
+        .join(" ");
+
     let command = [
         // graceful shutdown part
         COMMON_BASH_TRAP_FUNCTIONS.to_string(),
@@ -641,8 +1514,7 @@ fn build_server_rolegroup_statefulset(
         "prepare_signal_handlers".to_string(),
         format!("containerdebug --output={STACKABLE_LOG_DIR}/containerdebug-state.json --loop &"),
         // run process
-        // hard-coded memory limit to work around #147, TODO: migrate to our JVM resource handling framework
-        format!("java -Djava.security.properties={STACKABLE_CONFIG_DIR}/{JVM_SECURITY_PROPERTIES} -Xmx100M -jar hello-world.jar &"),
+        format!("java {jvm_args} -jar hello-world.jar &"),
         // graceful shutdown part
         "wait_for_termination $!".to_string(),
         create_vector_shutdown_file_command(STACKABLE_LOG_DIR),
@@ -667,31 +1539,17 @@ fn build_server_rolegroup_statefulset(
             STACKABLE_LOG_CONFIG_MOUNT_DIR,
         )
         .context(AddVolumeMountSnafu)?
-        .add_container_port(HTTP_PORT_NAME, HTTP_PORT.into())
+        .add_volume_mount(LISTENER_VOLUME_NAME, LISTENER_VOLUME_DIR)
+        .context(AddVolumeMountSnafu)?
+        .add_container_port(hello.http_port_name(), hello.http_port().into())
+        .add_container_port(METRICS_PORT_NAME, METRICS_PORT.into())
         .resources(merged_config.resources.clone().into())
-        .readiness_probe(Probe {
-            initial_delay_seconds: Some(10),
-            period_seconds: Some(10),
-            failure_threshold: Some(5),
-            tcp_socket: Some(TCPSocketAction {
-                port: IntOrString::String(HTTP_PORT_NAME.to_string()),
-                ..TCPSocketAction::default()
-            }),
-            ..Probe::default()
-        })
-        .liveness_probe(Probe {
-            initial_delay_seconds: Some(30),
-            period_seconds: Some(10),
-            tcp_socket: Some(TCPSocketAction {
-                port: IntOrString::String(HTTP_PORT_NAME.to_string()),
-                ..TCPSocketAction::default()
-            }),
-            ..Probe::default()
-        })
+        .readiness_probe(http_probe(READINESS_PATH, &merged_config.probes.readiness))
+        .liveness_probe(http_probe(LIVENESS_PATH, &merged_config.probes.liveness))
         .build();
 
-    let mut pod_builder = PodBuilder::new();
-    add_graceful_shutdown_config(merged_config, &mut pod_builder).context(GracefulShutdownSnafu)?;
+    add_graceful_shutdown_config(merged_config.graceful_shutdown_timeout, &mut pod_builder)
+        .context(GracefulShutdownSnafu)?;
 
     let metadata = ObjectMetaBuilder::new()
         .with_recommended_labels(build_recommended_labels(
@@ -701,6 +1559,10 @@ fn build_server_rolegroup_statefulset(
             &role_group_ref.role_group,
         ))
         .context(MetadataBuildSnafu)?
+        .with_annotation(
+            Annotation::try_from((CONFIG_HASH_ANNOTATION, config_hash))
+                .context(AnnotationBuildSnafu)?,
+        )
         .build();
 
     pod_builder
@@ -727,9 +1589,45 @@ fn build_server_rolegroup_statefulset(
             ..Volume::default()
         })
         .context(AddVolumeSnafu)?
+        .add_listener_volume_by_listener_name(
+            LISTENER_VOLUME_NAME,
+            &group_listener_name(hello, hello_role),
+            &Labels::recommended(build_recommended_labels(
+                hello,
+                &resolved_product_image.app_version_label,
+                &role_group_ref.role,
+                &role_group_ref.role_group,
+            ))
+            .context(LabelBuildSnafu)?,
+        )
+        .context(AddListenerVolumeSnafu)?
         .affinity(&merged_config.affinity)
         .service_account_name(sa_name);
 
+    if let Some(server_secret_class) = hello.server_secret_class() {
+        pod_builder
+            .add_volume(
+                VolumeBuilder::new(STACKABLE_SERVER_TLS_DIR_NAME)
+                    .ephemeral(
+                        SecretOperatorVolumeSourceBuilder::new(server_secret_class)
+                            .with_pod_scope()
+                            .with_node_scope()
+                            .with_service_scope(
+                                hello
+                                    .server_role_service_name()
+                                    .context(GlobalServiceNameNotFoundSnafu)?,
+                            )
+                            .with_listener_volume_scope(LISTENER_VOLUME_NAME)
+                            .with_format(SecretFormat::TlsPkcs12)
+                            .with_tls_pkcs12_password(STACKABLE_TLS_STORE_PASSWORD)
+                            .build()
+                            .context(TlsCertSecretClassVolumeBuildSnafu)?,
+                    )
+                    .build(),
+            )
+            .context(AddVolumeSnafu)?;
+    }
+
     // .security_context(
     //     PodSecurityContextBuilder::new()
     //         .run_as_user(HELLO_UID)
@@ -790,69 +1688,284 @@ fn build_server_rolegroup_statefulset(
     pod_template.merge_from(role.config.pod_overrides.clone());
     pod_template.merge_from(role_group.config.pod_overrides.clone());
 
-    Ok(StatefulSet {
-        metadata: ObjectMetaBuilder::new()
-            .name_and_namespace(hello)
-            .name(role_group_ref.object_name())
-            .ownerreference_from_resource(hello, None, Some(true))
-            .context(ObjectMissingMetadataForOwnerRefSnafu)?
-            .with_recommended_labels(build_recommended_labels(
+    let workload_metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(hello)
+        .name(role_group_ref.object_name())
+        .ownerreference_from_resource(hello, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            hello,
+            &resolved_product_image.app_version_label,
+            &role_group_ref.role,
+            &role_group_ref.role_group,
+        ))
+        .context(MetadataBuildSnafu)?
+        .build();
+    // The replicas are managed by the HorizontalPodAutoscaler instead
+    let replicas = if merged_config.autoscaling.is_some() {
+        None
+    } else {
+        role_group.replicas.map(i32::from)
+    };
+    let selector = LabelSelector {
+        match_labels: Some(
+            Labels::role_group_selector(
                 hello,
-                &resolved_product_image.app_version_label,
+                APP_NAME,
                 &role_group_ref.role,
                 &role_group_ref.role_group,
-            ))
-            .context(MetadataBuildSnafu)?
-            .build(),
-        spec: Some(StatefulSetSpec {
-            pod_management_policy: Some("Parallel".to_string()),
-            replicas: role_group.replicas.map(i32::from),
-            selector: LabelSelector {
-                match_labels: Some(
-                    Labels::role_group_selector(
-                        hello,
-                        APP_NAME,
-                        &role_group_ref.role,
-                        &role_group_ref.role_group,
-                    )
-                    .context(LabelBuildSnafu)?
-                    .into(),
-                ),
-                ..LabelSelector::default()
-            },
-            service_name: role_group_ref.object_name(),
-            template: pod_template,
-            volume_claim_templates: Some(vec![merged_config
-                .resources
-                .storage
-                .data
-                .build_pvc("data", Some(vec!["ReadWriteOnce"]))]),
-            ..StatefulSetSpec::default()
+            )
+            .context(LabelBuildSnafu)?
+            .into(),
+        ),
+        ..LabelSelector::default()
+    };
+
+    Ok(match merged_config.workload {
+        HelloWorkload::StatefulSet => RoleGroupWorkload::StatefulSet(StatefulSet {
+            metadata: workload_metadata,
+            spec: Some(StatefulSetSpec {
+                pod_management_policy: Some(merged_config.pod_management_policy.to_string()),
+                replicas,
+                selector,
+                service_name: role_group_ref.object_name(),
+                template: pod_template,
+                volume_claim_templates: Some(vec![merged_config
+                    .resources
+                    .storage
+                    .data
+                    .build_pvc("data", Some(vec!["ReadWriteOnce"]))]),
+                ..StatefulSetSpec::default()
+            }),
+            status: None,
+        }),
+        HelloWorkload::Deployment => RoleGroupWorkload::Deployment(Deployment {
+            metadata: workload_metadata,
+            spec: Some(DeploymentSpec {
+                replicas,
+                selector,
+                template: pod_template,
+                ..DeploymentSpec::default()
+            }),
+            status: None,
         }),
-        status: None,
     })
 }
 
+/// Probes the given health endpoint of Spring Boot Actuator.
+///
+/// Actuator is served on the metrics port, which is neither encrypted nor authenticated, so the
+/// probes work regardless of the TLS and authentication settings.
+fn http_probe(path: &str, probe: &ProbeConfig) -> Probe {
+    Probe {
+        initial_delay_seconds: Some(probe.initial_delay_seconds.into()),
+        period_seconds: Some(probe.period_seconds.into()),
+        timeout_seconds: Some(probe.timeout_seconds.into()),
+        failure_threshold: Some(probe.failure_threshold.into()),
+        http_get: Some(HTTPGetAction {
+            path: Some(path.to_owned()),
+            port: IntOrString::String(METRICS_PORT_NAME.to_owned()),
+            scheme: Some("HTTP".to_owned()),
+            ..HTTPGetAction::default()
+        }),
+        ..Probe::default()
+    }
+}
+
+/// Hashes the content of the given [`ConfigMap`]s mounted by the Pods of a role group.
+///
+/// The Pods do not pick up changes of mounted configuration files on their own, so the hash is
+/// added to the Pod template, which rolls out the Pods whenever the configuration changes.
+fn config_hash<'a>(config_maps: impl IntoIterator<Item = &'a ConfigMap>) -> String {
+    let mut hasher = FnvHasher::default();
+    for config_map in config_maps {
+        config_map.data.hash(&mut hasher);
+        for (key, value) in config_map.binary_data.iter().flatten() {
+            key.hash(&mut hasher);
+            value.0.hash(&mut hasher);
+        }
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Whether the given [`ConfigMap`] is used as custom log configuration by the [`HelloCluster`],
+/// so that changes to it are rolled out as well.
+///
+/// This is checked for every change of any ConfigMap, so the configs of the role and its role
+/// groups are only looked up instead of merged. A custom log ConfigMap, which is overridden by
+/// the role groups, therefore still matches, which only causes a needless reconciliation.
+pub fn references_config_map(
+    hello: &DeserializeGuard<HelloCluster>,
+    config_map: &DeserializeGuard<ConfigMap>,
+) -> bool {
+    let Ok(hello) = &hello.0 else {
+        return false;
+    };
+    if hello.namespace() != config_map.namespace() {
+        return false;
+    }
+
+    let servers = &hello.spec.servers;
+    let config_map_name = config_map.name_any();
+    iter::once(&servers.config.config)
+        .chain(
+            servers
+                .role_groups
+                .values()
+                .map(|role_group| &role_group.config.config),
+        )
+        .any(|config| {
+            custom_log_config_map_fragment(&config.logging, &Container::Hello)
+                == Some(config_map_name.as_str())
+        })
+}
+
+/// The replicas of the role group, as reported by the applied workload
+fn role_group_status(
+    role_group_ref: &RoleGroupRef<HelloCluster>,
+    workload: &RoleGroupWorkload,
+) -> HelloRoleGroupStatus {
+    let (desired_replicas, ready_replicas, updated_replicas, partition) = match workload {
+        RoleGroupWorkload::StatefulSet(statefulset) => {
+            let status = statefulset.status.as_ref();
+            (
+                statefulset
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.replicas)
+                    .or(status.map(|status| status.replicas)),
+                status.and_then(|status| status.ready_replicas),
+                status.and_then(|status| status.updated_replicas),
+                rollout::partition(statefulset),
+            )
+        }
+        RoleGroupWorkload::Deployment(deployment) => {
+            let status = deployment.status.as_ref();
+            (
+                deployment
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.replicas)
+                    .or(status.and_then(|status| status.replicas)),
+                status.and_then(|status| status.ready_replicas),
+                status.and_then(|status| status.updated_replicas),
+                None,
+            )
+        }
+    };
+
+    HelloRoleGroupStatus {
+        role: role_group_ref.role.clone(),
+        role_group: role_group_ref.role_group.clone(),
+        desired_replicas: desired_replicas.unwrap_or_default(),
+        ready_replicas: ready_replicas.unwrap_or_default(),
+        updated_replicas: updated_replicas.unwrap_or_default(),
+        partition,
+    }
+}
+
+/// Whether the workload has caught up with its spec and all of its Pods are updated and ready
+fn is_rolled_out(workload: &RoleGroupWorkload) -> bool {
+    match workload {
+        RoleGroupWorkload::StatefulSet(statefulset) => {
+            statefulset.status.as_ref().is_some_and(|status| {
+                let desired_replicas = statefulset
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.replicas)
+                    .unwrap_or(status.replicas);
+                status.observed_generation == statefulset.metadata.generation
+                    && status.updated_replicas.unwrap_or_default() >= desired_replicas
+                    && status.ready_replicas.unwrap_or_default() >= desired_replicas
+            })
+        }
+        RoleGroupWorkload::Deployment(deployment) => {
+            deployment.status.as_ref().is_some_and(|status| {
+                let desired_replicas = deployment
+                    .spec
+                    .as_ref()
+                    .and_then(|spec| spec.replicas)
+                    .or(status.replicas)
+                    .unwrap_or_default();
+                status.observed_generation == deployment.metadata.generation
+                    && status.replicas.unwrap_or_default() == desired_replicas
+                    && status.updated_replicas.unwrap_or_default() >= desired_replicas
+                    && status.ready_replicas.unwrap_or_default() >= desired_replicas
+            })
+        }
+    }
+}
+
+/// The current replicas and the label selector of the Pods of the workload, as expected by the
+/// `scale` subresource
+fn scale_status_of(workload: &RoleGroupWorkload) -> (i32, String) {
+    let (replicas, selector) = match workload {
+        RoleGroupWorkload::StatefulSet(statefulset) => (
+            statefulset.status.as_ref().map(|status| status.replicas),
+            statefulset.spec.as_ref().map(|spec| &spec.selector),
+        ),
+        RoleGroupWorkload::Deployment(deployment) => (
+            deployment
+                .status
+                .as_ref()
+                .and_then(|status| status.replicas),
+            deployment.spec.as_ref().map(|spec| &spec.selector),
+        ),
+    };
+    let selector = selector
+        .and_then(|selector| selector.match_labels.as_ref())
+        .map(|match_labels| {
+            match_labels
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .unwrap_or_default();
+
+    (replicas.unwrap_or_default(), selector)
+}
+
 pub fn error_policy(
     _obj: Arc<DeserializeGuard<HelloCluster>>,
     error: &Error,
     _ctx: Arc<Ctx>,
 ) -> Action {
     match error {
-        Error::InvalidHelloCluster { .. } => Action::await_change(),
+        Error::InvalidHelloCluster { .. }
+        | Error::InvalidHttpPort { .. }
+        | Error::InvalidGreeting { .. }
+        | Error::InvalidCanary { .. }
+        | Error::InvalidIngress { .. }
+        | Error::InvalidAutoscaling { .. }
+        | Error::InvalidEdgeConfig { .. }
+        | Error::InvalidEdgeCache { .. } => Action::await_change(),
         _ => Action::requeue(Duration::from_secs(5)),
     }
 }
 
-fn service_ports() -> Vec<ServicePort> {
+fn service_ports(hello: &HelloCluster) -> Vec<ServicePort> {
     vec![ServicePort {
-        name: Some(HTTP_PORT_NAME.to_string()),
-        port: HTTP_PORT.into(),
+        name: Some(hello.http_port_name().to_string()),
+        port: hello.http_port().into(),
         protocol: Some("TCP".to_string()),
         ..ServicePort::default()
     }]
 }
 
+/// The metrics are served without TLS and authentication, so they are only exposed by the
+/// rolegroup [`Service`]s, which are scraped by Prometheus, but not by the role [`Service`]
+fn rolegroup_service_ports(hello: &HelloCluster) -> Vec<ServicePort> {
+    let mut ports = service_ports(hello);
+    ports.push(ServicePort {
+        name: Some(METRICS_PORT_NAME.to_string()),
+        port: METRICS_PORT.into(),
+        protocol: Some("TCP".to_string()),
+        ..ServicePort::default()
+    });
+    ports
+}
+
 /// Creates recommended `ObjectLabels` to be used in deployed resources
 pub fn build_recommended_labels<'a, T>(
     owner: &'a T,
@@ -870,3 +1983,299 @@ pub fn build_recommended_labels<'a, T>(
         role_group,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+    use stackable_operator::{k8s_openapi::ByteString, kube::runtime::reflector::ObjectRef};
+
+    fn role_group_ref(role: &HelloRole, role_group: &str) -> RoleGroupRef<HelloCluster> {
+        RoleGroupRef {
+            cluster: ObjectRef::new("hello-world").within("default"),
+            role: role.to_string(),
+            role_group: role_group.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_statefulset_role_group_status() {
+        let statefulset: StatefulSet = serde_yaml::from_str(
+            r#"
+            metadata:
+              name: hello-world-server-default
+            spec:
+              replicas: 3
+              selector: {}
+              serviceName: hello-world-server-default
+              template: {}
+              updateStrategy:
+                type: RollingUpdate
+                rollingUpdate:
+                  partition: 1
+            status:
+              replicas: 2
+              readyReplicas: 2
+              updatedReplicas: 1
+            "#,
+        )
+        .expect("illegal test input");
+
+        assert_eq!(
+            role_group_status(
+                &role_group_ref(&HelloRole::Server, "default"),
+                &RoleGroupWorkload::StatefulSet(statefulset),
+            ),
+            HelloRoleGroupStatus {
+                role: "server".to_owned(),
+                role_group: "default".to_owned(),
+                desired_replicas: 3,
+                ready_replicas: 2,
+                updated_replicas: 1,
+                partition: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn test_deployment_role_group_status() {
+        // Without replicas in the spec, e.g. when they are managed by a
+        // HorizontalPodAutoscaler, the replicas are taken from the status
+        let deployment: Deployment = serde_yaml::from_str(
+            r#"
+            metadata:
+              name: hello-world-edge-default
+            spec:
+              selector: {}
+              template: {}
+            status:
+              replicas: 4
+              readyReplicas: 3
+            "#,
+        )
+        .expect("illegal test input");
+
+        assert_eq!(
+            role_group_status(
+                &role_group_ref(&HelloRole::Edge, "default"),
+                &RoleGroupWorkload::Deployment(deployment),
+            ),
+            HelloRoleGroupStatus {
+                role: "edge".to_owned(),
+                role_group: "default".to_owned(),
+                desired_replicas: 4,
+                ready_replicas: 3,
+                updated_replicas: 0,
+                partition: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_role_group_status_before_the_workload_is_processed() {
+        let statefulset: StatefulSet = serde_yaml::from_str(
+            r#"
+            metadata:
+              name: hello-world-server-default
+            spec:
+              selector: {}
+              serviceName: hello-world-server-default
+              template: {}
+            "#,
+        )
+        .expect("illegal test input");
+
+        assert_eq!(
+            role_group_status(
+                &role_group_ref(&HelloRole::Server, "default"),
+                &RoleGroupWorkload::StatefulSet(statefulset),
+            ),
+            HelloRoleGroupStatus {
+                role: "server".to_owned(),
+                role_group: "default".to_owned(),
+                desired_replicas: 0,
+                ready_replicas: 0,
+                updated_replicas: 0,
+                partition: None,
+            }
+        );
+    }
+
+    fn config_map(name: &str, namespace: &str, data: &str) -> ConfigMap {
+        ConfigMapBuilder::new()
+            .metadata(
+                ObjectMetaBuilder::new()
+                    .name(name)
+                    .namespace(namespace)
+                    .build(),
+            )
+            .add_data("logback.xml", data)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_config_hash() {
+        let config = config_map("hello-world-server-default", "default", "<configuration/>");
+        let custom_log_config = config_map("custom-log", "default", "<configuration/>");
+
+        assert_eq!(
+            config_hash([&config]),
+            config_hash([&config_map("renamed", "other", "<configuration/>")]),
+            "only the content is hashed"
+        );
+        assert_ne!(
+            config_hash([&config]),
+            config_hash([&config_map(
+                "hello-world-server-default",
+                "default",
+                "<configuration debug=\"true\"/>"
+            )])
+        );
+        assert_ne!(
+            config_hash([&config]),
+            config_hash([&config, &custom_log_config])
+        );
+
+        let mut binary_config = config.clone();
+        binary_config.binary_data =
+            Some([("keystore.p12".to_owned(), ByteString(vec![1, 2, 3]))].into());
+        assert_ne!(config_hash([&config]), config_hash([&binary_config]));
+    }
+
+    fn hello_with_logging(servers: &str) -> DeserializeGuard<HelloCluster> {
+        let input = format!(
+            r#"
+            apiVersion: hello-world.stackable.tech/v1alpha2
+            kind: HelloCluster
+            metadata:
+              name: hello-world
+              namespace: default
+            spec:
+              image:
+                productVersion: 0.1.0
+              clusterConfig:
+                greeting:
+                  recipient: Stackable
+                  color: blue
+              servers: {servers}
+            "#
+        );
+        DeserializeGuard(Ok(serde_yaml::from_str(&input).expect("illegal test input")))
+    }
+
+    #[rstest]
+    #[case::role(
+        "{config: {logging: {containers: {hello: {custom: {configMap: custom-log}}}}}, roleGroups: {default: {replicas: 1}}}",
+        true
+    )]
+    #[case::role_group(
+        "{roleGroups: {default: {replicas: 1}, other: {replicas: 1, config: {logging: {containers: {hello: {custom: {configMap: custom-log}}}}}}}}",
+        true
+    )]
+    #[case::other_config_map(
+        "{config: {logging: {containers: {hello: {custom: {configMap: other-log}}}}}, roleGroups: {default: {replicas: 1}}}",
+        false
+    )]
+    #[case::other_container(
+        "{config: {logging: {containers: {vector: {custom: {configMap: custom-log}}}}}, roleGroups: {default: {replicas: 1}}}",
+        false
+    )]
+    #[case::automatic_logging(
+        "{config: {logging: {containers: {hello: {console: {level: INFO}}}}}, roleGroups: {default: {replicas: 1}}}",
+        false
+    )]
+    fn test_references_config_map(#[case] servers: &str, #[case] expected: bool) {
+        let hello = hello_with_logging(servers);
+        let custom_log = DeserializeGuard(Ok(config_map("custom-log", "default", "")));
+
+        assert_eq!(references_config_map(&hello, &custom_log), expected);
+    }
+
+    #[test]
+    fn test_references_config_map_in_other_namespace() {
+        let hello = hello_with_logging(
+            "{config: {logging: {containers: {hello: {custom: {configMap: custom-log}}}}}, roleGroups: {default: {replicas: 1}}}",
+        );
+        let custom_log = DeserializeGuard(Ok(config_map("custom-log", "other", "")));
+
+        assert!(!references_config_map(&hello, &custom_log));
+    }
+
+    #[rstest]
+    #[case::default(8080, true)]
+    #[case::lowest_unprivileged(1024, true)]
+    #[case::privileged(80, false)]
+    #[case::zero(0, false)]
+    #[case::metrics_port(METRICS_PORT, false)]
+    fn test_validate_http_port(#[case] port: u16, #[case] valid: bool) {
+        let input = format!(
+            r#"
+            apiVersion: hello-world.stackable.tech/v1alpha2
+            kind: HelloCluster
+            metadata:
+              name: hello-world
+              namespace: default
+            spec:
+              image:
+                productVersion: 0.1.0
+              clusterConfig:
+                greeting:
+                  recipient: Stackable
+                  color: blue
+                httpPort: {port}
+              servers:
+                roleGroups:
+                  default:
+                    replicas: 1
+            "#
+        );
+        let hello: HelloCluster = serde_yaml::from_str(&input).expect("illegal test input");
+
+        assert_eq!(validate_http_port(&hello).is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case::binary_unit("128Mi", true)]
+    #[case::without_unit("128", false)]
+    fn test_validate_edges(#[case] max_size: &str, #[case] valid: bool) {
+        let input = format!(
+            r#"
+            apiVersion: hello-world.stackable.tech/v1alpha2
+            kind: HelloCluster
+            metadata:
+              name: hello-world
+              namespace: default
+            spec:
+              image:
+                productVersion: 0.1.0
+              clusterConfig:
+                greeting:
+                  recipient: Stackable
+                  color: blue
+              servers:
+                roleGroups:
+                  default:
+                    replicas: 1
+              edges:
+                roleGroups:
+                  default:
+                    replicas: 1
+                  small:
+                    replicas: 1
+                    config:
+                      cache:
+                        maxSize: "{max_size}"
+            "#
+        );
+        let hello: HelloCluster = serde_yaml::from_str(&input).expect("illegal test input");
+
+        let result = validate_edges(&hello);
+
+        assert_eq!(result.is_ok(), valid);
+        if let Err(error) = result {
+            assert!(matches!(error, Error::InvalidEdgeCache { .. }));
+        }
+    }
+}
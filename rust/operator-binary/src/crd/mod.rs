@@ -0,0 +1,1511 @@
+//! This file contains the definition of all the custom resources that this Operator manages.
+//! In this case, it is only the `HelloCluster`.
+//!
+//! When writing a new Operator, this is often a good starting point. Edits made here will ripple
+//! through the codebase, so it's easy to follow up from here.
+//!
+//! The `HelloCluster` is served in the versions [`v1alpha1`] and [`v1alpha2`]. The operator works
+//! with the latest version, which is also the storage version. Objects of older versions are
+//! converted by the conversion webhook, see [`crate::webhook`].
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_operator::{
+    commons::{
+        affinity::StackableAffinity,
+        authentication::ClientAuthenticationDetails,
+        opa::OpaConfig,
+        product_image_selection::ProductImage,
+        resources::{
+            CpuLimitsFragment, MemoryLimitsFragment, NoRuntimeLimits, NoRuntimeLimitsFragment,
+            PvcConfig, PvcConfigFragment, Resources, ResourcesFragment,
+        },
+    },
+    config::{
+        fragment::{self, Fragment, ValidationError},
+        merge::{Atomic, Merge},
+    },
+    k8s_openapi::{
+        apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition,
+        apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::Time},
+    },
+    kube::{
+        core::crd::{merge_crds, MergeError},
+        runtime::reflector::ObjectRef,
+        CustomResourceExt, ResourceExt,
+    },
+    product_config_utils::{self, Configuration},
+    product_logging::{self, spec::Logging},
+    role_utils::{
+        GenericProductSpecificCommonConfig, GenericRoleConfig, JavaCommonConfig, Role, RoleGroup,
+        RoleGroupRef,
+    },
+    schemars::{self, JsonSchema},
+    status::condition::{ClusterCondition, HasStatusCondition},
+    time::Duration,
+    utils::cluster_info::KubernetesClusterInfo,
+};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+use crate::affinity::get_affinity;
+
+pub mod v1alpha1;
+pub mod v1alpha2;
+
+pub use v1alpha2::{HelloCluster, HelloClusterSpec};
+
+pub const APP_NAME: &str = "hello-world";
+// directories
+pub const STACKABLE_CONFIG_DIR: &str = "/stackable/config";
+pub const STACKABLE_CONFIG_DIR_NAME: &str = "config";
+pub const STACKABLE_LOG_DIR: &str = "/stackable/log";
+pub const STACKABLE_LOG_DIR_NAME: &str = "log";
+pub const STACKABLE_LOG_CONFIG_MOUNT_DIR: &str = "/stackable/mount/log-config";
+pub const STACKABLE_LOG_CONFIG_MOUNT_DIR_NAME: &str = "log-config-mount";
+pub const STACKABLE_SERVER_TLS_DIR: &str = "/stackable/server_tls";
+pub const STACKABLE_SERVER_TLS_DIR_NAME: &str = "server-tls";
+pub const STACKABLE_CACHE_DIR: &str = "/stackable/cache";
+pub const STACKABLE_CACHE_DIR_NAME: &str = "cache";
+// config file names
+pub const APPLICATION_PROPERTIES: &str = "application.properties";
+pub const LOGBACK_XML: &str = "logback.xml";
+pub const HELLO_WORLD_LOG_FILE: &str = "hello-world.log4j.xml"; // the extension .log4j.xml is important!
+pub const JVM_SECURITY_PROPERTIES: &str = "security.properties";
+pub const NGINX_CONF: &str = "nginx.conf";
+// config properties
+pub const SERVER_PORT: &str = "server.port";
+pub const LOGGING_CONFIG: &str = "logging.config";
+pub const GREETING_RECIPIENT: &str = "greeting.recipient";
+pub const GREETING_COLOR: &str = "greeting.color";
+pub const MANAGEMENT_SERVER_PORT: &str = "management.server.port";
+pub const MANAGEMENT_SERVER_SSL_ENABLED: &str = "management.server.ssl.enabled";
+pub const MANAGEMENT_ENDPOINTS_EXPOSURE: &str = "management.endpoints.web.exposure.include";
+pub const MANAGEMENT_PROMETHEUS_ENABLED: &str = "management.endpoint.prometheus.enabled";
+pub const MANAGEMENT_HEALTH_PROBES_ENABLED: &str = "management.endpoint.health.probes.enabled";
+pub const OPA_URL: &str = "greeting.authorization.opa.url";
+pub const SERVER_SSL_ENABLED: &str = "server.ssl.enabled";
+pub const SERVER_SSL_KEY_STORE: &str = "server.ssl.key-store";
+pub const SERVER_SSL_KEY_STORE_PASSWORD: &str = "server.ssl.key-store-password";
+pub const SERVER_SSL_KEY_STORE_TYPE: &str = "server.ssl.key-store-type";
+// TLS
+pub const STACKABLE_TLS_STORE_PASSWORD: &str = "changeit";
+pub const KEYSTORE_P12: &str = "keystore.p12";
+// default ports
+pub const HTTP_PORT_NAME: &str = "http";
+pub const HTTPS_PORT_NAME: &str = "https";
+pub const DEFAULT_HTTP_PORT: u16 = 8080;
+pub const METRICS_PORT_NAME: &str = "metrics";
+pub const METRICS_PORT: u16 = 8081;
+pub const METRICS_PATH: &str = "/actuator/prometheus";
+pub const READINESS_PATH: &str = "/actuator/health/readiness";
+pub const LIVENESS_PATH: &str = "/actuator/health/liveness";
+
+/// Leaves enough room for the non-heap memory of the JVM within the default memory limit
+const DEFAULT_JVM_HEAP_PERCENTAGE: u8 = 40;
+const DEFAULT_HELLO_WORLD_GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_minutes_unchecked(2);
+const DEFAULT_CANARY_SOAK_TIME: Duration = Duration::from_minutes_unchecked(10);
+const DEFAULT_EDGE_GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_EDGE_CACHE_VALID: Duration = Duration::from_secs(10);
+/// The nginx version of the default image of the reverse proxies
+const DEFAULT_EDGE_VERSION: &str = "1.27.3";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("fragment validation failure"))]
+    FragmentValidationFailure { source: ValidationError },
+    #[snafu(display("unknown role {role}. Should be one of {roles:?}"))]
+    UnknownHelloRole {
+        source: strum::ParseError,
+        role: String,
+        roles: Vec<String>,
+    },
+    #[snafu(display("the role group {role_group} is not defined"))]
+    CannotRetrieveHelloRoleGroup { role_group: String },
+    #[snafu(display("the {role} role is not configured like the servers"))]
+    NotAServerRole { role: HelloRole },
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloClusterConfig {
+    /// The greeting served by all servers, unless it is overridden for a role or role group.
+    pub greeting: HelloGreeting,
+    /// Authentication of clients requesting the greeting.
+    /// Currently a single [AuthenticationClass](https://docs.stackable.tech/home/stable/concepts/authentication)
+    /// using the `static`, `ldap` or `oidc` provider is supported.
+    #[serde(default)]
+    pub authentication: Vec<ClientAuthenticationDetails>,
+    /// Authorization of clients requesting the greeting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization: Option<HelloAuthorization>,
+    /// Name of the Vector aggregator discovery ConfigMap.
+    /// It must contain the key `ADDRESS` with the address of the Vector aggregator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_aggregator_config_map_name: Option<String>,
+    /// This field controls which [ListenerClass](https://docs.stackable.tech/home/stable/listener-operator/listenerclass.html)
+    /// is used to expose the servers. The listener-operator provides the following ListenerClasses
+    /// out of the box, but any other ListenerClass present in the Kubernetes cluster can be used as well:
+    ///
+    /// * cluster-internal: Only reachable from within the Kubernetes cluster
+    ///
+    /// * external-unstable: Reachable from outside the Kubernetes cluster, the address may change
+    ///
+    /// * external-stable: Reachable from outside the Kubernetes cluster with a stable address
+    #[serde(default = "default_listener_class")]
+    pub listener_class: String,
+    /// TLS encryption settings for the servers
+    #[serde(default)]
+    pub tls: HelloTls,
+    /// The port serving the greeting. It is used by the containers, the Services and the
+    /// Listener alike and must differ from the metrics port 8081. The containers do not run as
+    /// root, so it must be at least 1024.
+    #[serde(default = "default_http_port")]
+    #[schemars(range(min = 1024))]
+    pub http_port: u16,
+    /// Rolls out changes of the spec to a single role group first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<HelloCanary>,
+    /// How long the rollout of a change of the spec may go without progress, e.g. `10m`. The
+    /// deadline starts anew whenever a Pod of the changed role groups becomes updated or ready,
+    /// so it does not limit the duration of the whole rollout. Once it runs out, all role groups
+    /// are rolled back to the last known good spec until the spec is changed again. Changes are
+    /// never rolled back if it is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_deadline: Option<Duration>,
+    /// Exposes the role Service of the servers via an Ingress or a Gateway API HTTPRoute. It
+    /// cannot be combined with `tls.serverSecretClass`, as the Ingress and the HTTPRoute connect to
+    /// the servers via HTTP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress: Option<HelloIngress>,
+}
+
+/// The Ingress or HTTPRoute is named after the [`HelloCluster`] and routes all requests for the
+/// host and path to the role Service of the servers.
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloIngress {
+    /// The host serving the greeting, e.g. `hello.example.com`
+    pub host: String,
+    /// The path prefix serving the greeting. It must start with a `/`. Defaults to `/`.
+    #[serde(default = "HelloIngress::default_path")]
+    pub path: String,
+    /// Name of the IngressClass of the Ingress. The default IngressClass of the Kubernetes
+    /// cluster is used if it is not set. It is ignored if an HTTPRoute is created instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_class_name: Option<String>,
+    /// Creates a Gateway API HTTPRoute attached to the given Gateway instead of an Ingress.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_route: Option<HelloHttpRoute>,
+}
+
+impl HelloIngress {
+    fn default_path() -> String {
+        "/".to_owned()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloHttpRoute {
+    /// The name of the Gateway the HTTPRoute is attached to
+    pub gateway_name: String,
+    /// The namespace of the Gateway. Defaults to the namespace of the [`HelloCluster`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway_namespace: Option<String>,
+}
+
+/// The canary role group receives changes of the spec first. The other role groups keep the last
+/// known good spec until the canary role group was ready for the whole soak time.
+///
+/// The `replicas` of the other role groups are always taken from the current spec, so that scaling
+/// does not wait for the canary. Changes of only the `replicas` or the `clusterOperation` are
+/// applied to all role groups at once and do not start a soak.
+///
+/// The role groups which keep the last known good spec also resolve the referenced
+/// AuthenticationClass, OPA and Vector aggregator discovery ConfigMaps from it. The resources
+/// shared by all role groups, i.e. the role Service, the Listener, the discovery ConfigMap, the
+/// Ingress and the edge role, always follow the current spec.
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloCanary {
+    /// The name of the canary role group
+    pub role_group: String,
+    /// How long the canary role group must be ready before the other role groups are updated,
+    /// e.g. `10m` or `1h`. Defaults to `10m`.
+    #[serde(default = "HelloCanary::default_soak_time")]
+    pub soak_time: Duration,
+}
+
+impl HelloCanary {
+    fn default_soak_time() -> Duration {
+        DEFAULT_CANARY_SOAK_TIME
+    }
+}
+
+pub(crate) fn default_listener_class() -> String {
+    "cluster-internal".to_owned()
+}
+
+fn default_http_port() -> u16 {
+    DEFAULT_HTTP_PORT
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloGreeting {
+    /// Who is greeted, e.g. `Stackable`.
+    pub recipient: String,
+    /// The color of the greeting as CSS color, e.g. `blue` or `#0000ff`.
+    pub color: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloAuthorization {
+    /// Configure the OPA stacklet [discovery ConfigMap](https://docs.stackable.tech/home/stable/concepts/service_discovery)
+    /// and the name of the Rego package containing your authorization rules.
+    /// The package defaults to the name of the HelloCluster.
+    /// Requests are only allowed if the `allow` rule of the package evaluates to `true`.
+    /// The rule receives the input `{"user": ..., "method": ..., "path": ...}`, where the user is
+    /// `null` for anonymous requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opa: Option<OpaConfig>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloTls {
+    /// Name of the [SecretClass](https://docs.stackable.tech/home/stable/secret-operator/secretclass.html)
+    /// providing the certificate the servers use to authenticate themselves against clients.
+    /// If set, the greeting is only served via HTTPS, otherwise it is served via plain HTTP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_secret_class: Option<String>,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    Display,
+    EnumIter,
+    Eq,
+    Hash,
+    JsonSchema,
+    PartialEq,
+    Serialize,
+    EnumString,
+)]
+#[strum(serialize_all = "camelCase")]
+pub enum HelloRole {
+    #[strum(serialize = "server")]
+    Server,
+    #[strum(serialize = "edge")]
+    Edge,
+}
+
+impl HelloRole {
+    pub fn roles() -> Vec<String> {
+        let mut roles = vec![];
+        for role in Self::iter() {
+            roles.push(role.to_string())
+        }
+        roles
+    }
+
+    /// Metadata about a rolegroup
+    pub fn rolegroup_ref(
+        &self,
+        hello: &HelloCluster,
+        group_name: impl Into<String>,
+    ) -> RoleGroupRef<HelloCluster> {
+        RoleGroupRef {
+            cluster: ObjectRef::from_obj(hello),
+            role: self.to_string(),
+            role_group: group_name.into(),
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Deserialize,
+    Display,
+    Eq,
+    EnumIter,
+    JsonSchema,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Container {
+    Hello,
+    Vector,
+}
+
+#[derive(Clone, Debug, Default, JsonSchema, PartialEq, Fragment)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct ServerStorageConfig {
+    #[fragment_attrs(serde(default))]
+    pub data: PvcConfig,
+}
+
+/// The greeting served by a role or role group
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct GreetingConfig {
+    /// Who is greeted. Defaults to `clusterConfig.greeting.recipient`.
+    pub recipient: String,
+    /// The color of the greeting. Defaults to `clusterConfig.greeting.color`.
+    pub color: String,
+}
+
+/// The probes of the server container, which query the health endpoints of Spring Boot Actuator
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct ProbesConfig {
+    /// Removes the Pod from the Services while it cannot serve greetings
+    #[fragment_attrs(serde(default))]
+    pub readiness: ProbeConfig,
+    /// Restarts the server container once it stopped responding
+    #[fragment_attrs(serde(default))]
+    pub liveness: ProbeConfig,
+}
+
+/// The timing of a probe, see the [Kubernetes documentation](https://kubernetes.io/docs/tasks/configure-pod-container/configure-liveness-readiness-startup-probes/#configure-probes)
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct ProbeConfig {
+    /// Seconds after the start of the container before the first probe
+    pub initial_delay_seconds: u16,
+    /// Seconds between two probes
+    pub period_seconds: u16,
+    /// Seconds after which a probe times out
+    pub timeout_seconds: u16,
+    /// Number of consecutive failed probes after which the probe is considered failed
+    pub failure_threshold: u16,
+}
+
+/// Memory settings of the JVM, which are derived from the memory limit of the container
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct JvmMemoryLimits {
+    /// The percentage of the memory limit which is used for the JVM heap.
+    /// The remaining memory is the budget of the non-heap memory of the JVM: 40% of it are used for
+    /// the metaspace, 20% for the code cache and 20% for direct buffers, the rest is left for
+    /// thread stacks and other native memory.
+    pub heap_percentage: u8,
+}
+
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct HelloConfig {
+    #[fragment_attrs(serde(default))]
+    pub resources: Resources<ServerStorageConfig, JvmMemoryLimits>,
+    #[fragment_attrs(serde(default))]
+    pub logging: Logging<Container>,
+    #[fragment_attrs(serde(default))]
+    pub affinity: StackableAffinity,
+    /// Time period Pods have to gracefully shut down, e.g. `30m`, `1h` or `2d`. Consult the operator documentation for details.
+    #[fragment_attrs(serde(default))]
+    pub graceful_shutdown_timeout: Option<Duration>,
+    /// Overrides of the greeting, e.g. to greet different audiences per role group.
+    #[fragment_attrs(serde(default))]
+    pub greeting: GreetingConfig,
+    /// The kind of workload running the Pods of the role group, either `StatefulSet` (the default)
+    /// or `Deployment`. A Deployment creates no PersistentVolumeClaims and the names of its Pods
+    /// are not stable, so they are not listed in the discovery ConfigMap.
+    #[fragment_attrs(serde(default))]
+    pub workload: HelloWorkload,
+    /// Whether the Pods of a StatefulSet are started and stopped one after another
+    /// (`OrderedReady`) or all at once (`Parallel`, the default).
+    /// It cannot be changed after the role group was created, such changes are rejected.
+    /// Deployments ignore it.
+    #[fragment_attrs(serde(default))]
+    pub pod_management_policy: PodManagementPolicy,
+    /// How changes to the Pods of a StatefulSet are rolled out. Deployments ignore it.
+    #[fragment_attrs(serde(default))]
+    pub rolling_update: RollingUpdateConfig,
+    /// The readiness and liveness probes of the server container.
+    /// Raise the delays if the JVM is killed by the liveness probe before it has started.
+    #[fragment_attrs(serde(default))]
+    pub probes: ProbesConfig,
+    /// Scales the role group automatically between `minReplicas` and `maxReplicas` based on the
+    /// CPU utilization. The `replicas` of the role group are ignored if this is set.
+    #[fragment_attrs(serde(default))]
+    pub autoscaling: Option<AutoscalingConfig>,
+}
+
+/// The kind of workload running the Pods of a role group
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Display, Eq, JsonSchema, PartialEq, Serialize,
+)]
+pub enum HelloWorkload {
+    /// Pods with stable names, each with its own PersistentVolumeClaim for the `data` volume
+    #[default]
+    StatefulSet,
+    /// Interchangeable Pods without any PersistentVolumeClaims
+    Deployment,
+}
+
+impl Atomic for HelloWorkload {}
+
+/// The order in which the Pods of a StatefulSet are started and stopped
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Display, Eq, JsonSchema, PartialEq, Serialize,
+)]
+pub enum PodManagementPolicy {
+    /// A Pod is only started once its predecessor is ready
+    OrderedReady,
+    /// All Pods are started at once
+    #[default]
+    Parallel,
+}
+
+impl Atomic for PodManagementPolicy {}
+
+/// The rollout of changes to the Pods of a StatefulSet.
+///
+/// The Pods are updated in descending order of their ordinals, starting with the highest one.
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct RollingUpdateConfig {
+    /// Only Pods with an ordinal greater than or equal to the partition are updated, the others
+    /// keep running the previous revision. Ignored if `staged` is set.
+    #[fragment_attrs(serde(default))]
+    pub partition: Option<u16>,
+    /// Rolls out changes one Pod at a time: the operator starts by updating the Pod with the
+    /// highest ordinal and lowers the partition by one whenever all Pods are ready again.
+    /// The progress is reported in the `roleGroups` of the status.
+    pub staged: bool,
+}
+
+#[derive(Clone, Debug, Default, JsonSchema, PartialEq, Fragment)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct EdgeStorageConfig {}
+
+/// The caching of the greeting by the reverse proxies
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct EdgeCacheConfig {
+    /// How long a greeting is served from the cache, e.g. `10s` or `5m`. `0s` disables caching.
+    /// Requests with credentials always bypass the cache.
+    pub valid: Duration,
+    /// The maximum size of the cache, which is kept in an emptyDir volume of this size.
+    pub max_size: Quantity,
+}
+
+/// The reverse proxies of the edge role. They serve the greeting on `clusterConfig.httpPort` like
+/// the servers.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloEdges {
+    /// The nginx image of the reverse proxies. Defaults to nginx from the Stackable image
+    /// registry, a custom image or registry can be used like for the `image` of the cluster.
+    #[serde(default = "HelloEdges::default_image")]
+    pub image: ProductImage,
+    #[serde(flatten)]
+    pub role: Role<EdgeConfigFragment, GenericRoleConfig, GenericProductSpecificCommonConfig>,
+}
+
+impl HelloEdges {
+    fn default_image() -> ProductImage {
+        serde_json::from_value(serde_json::json!({ "productVersion": DEFAULT_EDGE_VERSION }))
+            .expect("the default edge image must be valid")
+    }
+}
+
+/// The config of the reverse proxies of the edge role
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct EdgeConfig {
+    #[fragment_attrs(serde(default))]
+    pub resources: Resources<EdgeStorageConfig, NoRuntimeLimits>,
+    #[fragment_attrs(serde(default))]
+    pub affinity: StackableAffinity,
+    /// Time period Pods have to gracefully shut down, e.g. `30s` or `2m`.
+    #[fragment_attrs(serde(default))]
+    pub graceful_shutdown_timeout: Option<Duration>,
+    #[fragment_attrs(serde(default))]
+    pub cache: EdgeCacheConfig,
+}
+
+impl EdgeConfig {
+    fn default_config(hello: &HelloCluster) -> EdgeConfigFragment {
+        EdgeConfigFragment {
+            resources: ResourcesFragment {
+                cpu: CpuLimitsFragment {
+                    min: Some(Quantity("50m".to_owned())),
+                    max: Some(Quantity("200m".to_owned())),
+                },
+                memory: MemoryLimitsFragment {
+                    limit: Some(Quantity("64Mi".to_owned())),
+                    runtime_limits: NoRuntimeLimitsFragment {},
+                },
+                storage: EdgeStorageConfigFragment {},
+            },
+            affinity: get_affinity(&hello.name_any(), &HelloRole::Edge),
+            graceful_shutdown_timeout: Some(DEFAULT_EDGE_GRACEFUL_SHUTDOWN_TIMEOUT),
+            cache: EdgeCacheConfigFragment {
+                valid: Some(DEFAULT_EDGE_CACHE_VALID),
+                max_size: Some(Quantity("64Mi".to_owned())),
+            },
+        }
+    }
+}
+
+/// Settings of the HorizontalPodAutoscaler of a role group
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoscalingConfig {
+    /// The minimum number of replicas. Defaults to 1.
+    #[serde(default = "AutoscalingConfig::default_min_replicas")]
+    pub min_replicas: u16,
+    /// The maximum number of replicas
+    pub max_replicas: u16,
+    /// The average CPU utilization of the Pods, in percent of their requested CPU (`resources.cpu.min`),
+    /// which the autoscaler aims for. Defaults to 80.
+    #[serde(default = "AutoscalingConfig::default_target_cpu_utilization_percentage")]
+    pub target_cpu_utilization_percentage: u16,
+}
+
+impl AutoscalingConfig {
+    fn default_min_replicas() -> u16 {
+        1
+    }
+
+    fn default_target_cpu_utilization_percentage() -> u16 {
+        80
+    }
+}
+
+/// A role group overrides the `autoscaling` of the role as a whole
+impl Atomic for AutoscalingConfig {}
+
+impl HelloConfig {
+    fn default_config(hello: &HelloCluster, role: &HelloRole) -> HelloConfigFragment {
+        HelloConfigFragment {
+            resources: ResourcesFragment {
+                cpu: CpuLimitsFragment {
+                    min: Some(Quantity("100m".to_owned())),
+                    max: Some(Quantity("400m".to_owned())),
+                },
+                memory: MemoryLimitsFragment {
+                    limit: Some(Quantity("256Mi".to_owned())),
+                    runtime_limits: JvmMemoryLimitsFragment {
+                        heap_percentage: Some(DEFAULT_JVM_HEAP_PERCENTAGE),
+                    },
+                },
+                storage: ServerStorageConfigFragment {
+                    data: PvcConfigFragment {
+                        capacity: Some(Quantity("256Mi".to_owned())),
+                        storage_class: None,
+                        selectors: None,
+                    },
+                },
+            },
+            logging: product_logging::spec::default_logging(),
+            affinity: get_affinity(&hello.name_any(), role),
+            graceful_shutdown_timeout: Some(DEFAULT_HELLO_WORLD_GRACEFUL_SHUTDOWN_TIMEOUT),
+            greeting: GreetingConfigFragment {
+                recipient: Some(hello.spec.cluster_config.greeting.recipient.clone()),
+                color: Some(hello.spec.cluster_config.greeting.color.clone()),
+            },
+            probes: ProbesConfigFragment {
+                readiness: ProbeConfigFragment {
+                    initial_delay_seconds: Some(10),
+                    period_seconds: Some(10),
+                    timeout_seconds: Some(1),
+                    failure_threshold: Some(5),
+                },
+                liveness: ProbeConfigFragment {
+                    initial_delay_seconds: Some(30),
+                    period_seconds: Some(10),
+                    timeout_seconds: Some(1),
+                    failure_threshold: Some(3),
+                },
+            },
+            workload: Some(HelloWorkload::StatefulSet),
+            pod_management_policy: Some(PodManagementPolicy::Parallel),
+            rolling_update: RollingUpdateConfigFragment {
+                partition: None,
+                staged: Some(false),
+            },
+            autoscaling: None,
+        }
+    }
+}
+
+impl Configuration for HelloConfigFragment {
+    type Configurable = HelloCluster;
+
+    fn compute_env(
+        &self,
+        _hello: &Self::Configurable,
+        _role_name: &str,
+    ) -> Result<BTreeMap<String, Option<String>>, product_config_utils::Error> {
+        let result = BTreeMap::new();
+        // no ENV args necessary
+        Ok(result)
+    }
+
+    fn compute_cli(
+        &self,
+        _hello: &Self::Configurable,
+        _role_name: &str,
+    ) -> Result<BTreeMap<String, Option<String>>, product_config_utils::Error> {
+        let result = BTreeMap::new();
+        // No CLI args necessary
+        Ok(result)
+    }
+
+    fn compute_files(
+        &self,
+        hello: &Self::Configurable,
+        _role_name: &str,
+        file: &str,
+    ) -> Result<BTreeMap<String, Option<String>>, product_config_utils::Error> {
+        let mut result = BTreeMap::new();
+
+        if file == APPLICATION_PROPERTIES {
+            result.insert(SERVER_PORT.to_owned(), Some(hello.http_port().to_string()));
+            if hello.server_secret_class().is_some() {
+                result.insert(SERVER_SSL_ENABLED.to_owned(), Some("true".to_owned()));
+                result.insert(
+                    SERVER_SSL_KEY_STORE.to_owned(),
+                    Some(format!("{STACKABLE_SERVER_TLS_DIR}/{KEYSTORE_P12}")),
+                );
+                result.insert(
+                    SERVER_SSL_KEY_STORE_PASSWORD.to_owned(),
+                    Some(STACKABLE_TLS_STORE_PASSWORD.to_owned()),
+                );
+                result.insert(
+                    SERVER_SSL_KEY_STORE_TYPE.to_owned(),
+                    Some("PKCS12".to_owned()),
+                );
+            }
+            // The metrics are served on a separate port, which is always plain HTTP
+            result.insert(
+                MANAGEMENT_SERVER_PORT.to_owned(),
+                Some(METRICS_PORT.to_string()),
+            );
+            result.insert(
+                MANAGEMENT_SERVER_SSL_ENABLED.to_owned(),
+                Some("false".to_owned()),
+            );
+            result.insert(
+                MANAGEMENT_ENDPOINTS_EXPOSURE.to_owned(),
+                Some("health,prometheus".to_owned()),
+            );
+            result.insert(
+                MANAGEMENT_PROMETHEUS_ENABLED.to_owned(),
+                Some("true".to_owned()),
+            );
+            // The probes query the health groups, which are only enabled by default when Spring
+            // Boot detects that it runs on Kubernetes
+            result.insert(
+                MANAGEMENT_HEALTH_PROBES_ENABLED.to_owned(),
+                Some("true".to_owned()),
+            );
+            result.insert(
+                LOGGING_CONFIG.to_owned(),
+                Some(format!("{}/{}", STACKABLE_CONFIG_DIR, LOGBACK_XML)),
+            );
+        }
+
+        Ok(result)
+    }
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloClusterStatus {
+    pub conditions: Vec<ClusterCondition>,
+    /// The `metadata.generation` of the HelloCluster which was reconciled last.
+    /// The status reflects an older spec as long as it differs from the current generation.
+    /// It is only written by successful reconciliations and for invalid greetings. Other failures,
+    /// e.g. of the Kubernetes API, are reported as events and keep the previous status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+    /// The product version, as resolved from the `image`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_version: Option<String>,
+    /// The URL of the role Service, which load-balances over all servers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// The addresses under which the roles are reachable, as resolved by the listener-operator.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listener_addresses: Vec<HelloListenerAddress>,
+    /// The replicas of the role groups, as reported by their StatefulSets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub role_groups: Vec<HelloRoleGroupStatus>,
+    /// The current replicas of the cluster, as used by the `scale` subresource.
+    /// It is only set if the cluster consists of the single role group `default`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<i32>,
+    /// The label selector of the Pods, as used by the `scale` subresource.
+    /// It is only set if the cluster consists of the single role group `default`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// The spec which was rolled out to all role groups last, while all of them became ready.
+    /// Paused and stopped clusters keep the previous one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_known_good: Option<HelloLastKnownGood>,
+    /// The progress of the canary role group, while a change of the spec is rolled out to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<HelloCanaryStatus>,
+    /// The progress of a change of the spec, until it is rolled out to all role groups. It is
+    /// only tracked if a `progressDeadline` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<HelloRolloutStatus>,
+}
+
+/// A snapshot of a spec which was rolled out successfully
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloLastKnownGood {
+    /// The `metadata.generation` of the spec
+    pub generation: i64,
+    /// A hash of the spec without the replicas and the cluster operation. Changes of them are
+    /// applied to all role groups at once, so they are not rolled out.
+    #[serde(default)]
+    pub revision: String,
+    /// The spec as JSON. It is stored as string, so that its schema is not repeated in the status.
+    pub spec: String,
+}
+
+/// The progress of the rollout of a revision of the spec
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloRolloutStatus {
+    /// The `metadata.generation` of the spec which is rolled out
+    pub generation: i64,
+    /// The revision of the spec which is rolled out, see `lastKnownGood.revision`
+    pub revision: String,
+    /// When more Pods of the role groups rendered from this revision became updated or ready the
+    /// last time, or when the rollout started
+    pub last_progress_time: Time,
+    /// The number of updated Pods of the role groups rendered from this revision at the
+    /// `lastProgressTime`
+    #[serde(default)]
+    pub updated_replicas: i32,
+    /// The number of ready Pods of the role groups rendered from this revision at the
+    /// `lastProgressTime`
+    #[serde(default)]
+    pub ready_replicas: i32,
+    /// Whether the rollout missed the deadline and the role groups were rolled back to the last
+    /// known good spec
+    #[serde(default)]
+    pub failed: bool,
+}
+
+/// The progress of the canary role group for a revision of the spec
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloCanaryStatus {
+    /// The revision of the spec rolled out to the canary role group, see
+    /// `lastKnownGood.revision`
+    pub revision: String,
+    /// Since when the canary role group is completely rolled out and ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_since: Option<Time>,
+    /// Whether the soak time has passed and the spec is rolled out to all role groups
+    #[serde(default)]
+    pub promoted: bool,
+}
+
+/// The replicas of a role group
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloRoleGroupStatus {
+    pub role: String,
+    pub role_group: String,
+    /// The number of replicas the role group should have
+    pub desired_replicas: i32,
+    /// The number of replicas which are ready
+    pub ready_replicas: i32,
+    /// The number of replicas which already run the current revision of the Pod template.
+    /// The role group is fully rolled out, once all three numbers are equal.
+    pub updated_replicas: i32,
+    /// The partition of the rolling update of a StatefulSet, only Pods with an ordinal greater
+    /// than or equal to it are updated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition: Option<i32>,
+}
+
+/// An address of a role [`Listener`](stackable_operator::commons::listener::Listener)
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloListenerAddress {
+    /// The role which is reachable under this address
+    pub role: String,
+    /// The hostname or IP address
+    pub address: String,
+    /// The ports under which the role is reachable, by port name
+    pub ports: BTreeMap<String, i32>,
+}
+
+impl HasStatusCondition for HelloCluster {
+    fn conditions(&self) -> Vec<ClusterCondition> {
+        match &self.status {
+            Some(status) => status.conditions.clone(),
+            None => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display("object has no namespace associated"))]
+pub struct NoNamespaceError;
+
+/// The CRD containing all served versions of the `HelloCluster`, with the latest version being
+/// stored
+pub fn merged_crd() -> Result<CustomResourceDefinition, MergeError> {
+    merge_crds(
+        vec![v1alpha1::HelloCluster::crd(), v1alpha2::HelloCluster::crd()],
+        v1alpha2::VERSION,
+    )
+}
+
+/// A role of the [`HelloCluster`], regardless of the type of its config
+pub enum AnyRole<'a> {
+    Server(&'a Role<HelloConfigFragment, GenericRoleConfig, JavaCommonConfig>),
+    Edge(&'a Role<EdgeConfigFragment, GenericRoleConfig, GenericProductSpecificCommonConfig>),
+}
+
+impl<'a> AnyRole<'a> {
+    pub fn role_config(&self) -> &'a GenericRoleConfig {
+        match self {
+            AnyRole::Server(role) => &role.role_config,
+            AnyRole::Edge(role) => &role.role_config,
+        }
+    }
+
+    /// The replicas of the role groups by name. The role groups are ordered consistently, to avoid
+    /// spurious downstream rewrites.
+    pub fn role_groups(&self) -> BTreeMap<&'a String, Option<u16>> {
+        match self {
+            AnyRole::Server(role) => role
+                .role_groups
+                .iter()
+                .map(|(name, role_group)| (name, role_group.replicas))
+                .collect(),
+            AnyRole::Edge(role) => role
+                .role_groups
+                .iter()
+                .map(|(name, role_group)| (name, role_group.replicas))
+                .collect(),
+        }
+    }
+}
+
+impl HelloCluster {
+    /// Returns a reference to the role, if it is defined
+    pub fn role(&self, role: &HelloRole) -> Option<AnyRole<'_>> {
+        match role {
+            HelloRole::Server => Some(AnyRole::Server(&self.spec.servers)),
+            HelloRole::Edge => self
+                .spec
+                .edges
+                .as_ref()
+                .map(|edges| AnyRole::Edge(&edges.role)),
+        }
+    }
+
+    /// Returns a reference to the server role group. Raises an error if the role group is not
+    /// defined or belongs to another role.
+    pub fn role_group(
+        &self,
+        rolegroup_ref: &RoleGroupRef<HelloCluster>,
+    ) -> Result<RoleGroup<HelloConfigFragment, JavaCommonConfig>, Error> {
+        let role_variant =
+            HelloRole::from_str(&rolegroup_ref.role).with_context(|_| UnknownHelloRoleSnafu {
+                role: rolegroup_ref.role.to_owned(),
+                roles: HelloRole::roles(),
+            })?;
+        let role = match role_variant {
+            HelloRole::Server => &self.spec.servers,
+            HelloRole::Edge => return NotAServerRoleSnafu { role: role_variant }.fail(),
+        };
+        role.role_groups
+            .get(&rolegroup_ref.role_group)
+            .with_context(|| CannotRetrieveHelloRoleGroupSnafu {
+                role_group: rolegroup_ref.role_group.to_owned(),
+            })
+            .cloned()
+    }
+
+    /// The name of the SecretClass providing the server certificate, if TLS is enabled
+    pub fn server_secret_class(&self) -> Option<&str> {
+        self.spec.cluster_config.tls.server_secret_class.as_deref()
+    }
+
+    /// The OPA configuration used to authorize greeting requests, if any
+    pub fn opa_config(&self) -> Option<&OpaConfig> {
+        self.spec
+            .cluster_config
+            .authorization
+            .as_ref()
+            .and_then(|authorization| authorization.opa.as_ref())
+    }
+
+    /// The port serving the greeting
+    pub fn http_port(&self) -> u16 {
+        self.spec.cluster_config.http_port
+    }
+
+    /// The name of the port serving the greeting, which depends on whether TLS is enabled
+    pub fn http_port_name(&self) -> &'static str {
+        if self.server_secret_class().is_some() {
+            HTTPS_PORT_NAME
+        } else {
+            HTTP_PORT_NAME
+        }
+    }
+
+    /// The name of the role-level load-balanced Kubernetes `Service`
+    pub fn server_role_service_name(&self) -> Option<&str> {
+        self.metadata.name.as_deref()
+    }
+
+    /// The name of the `Service` load-balancing over the reverse proxies of the edge role
+    pub fn edge_role_service_name(&self) -> Option<String> {
+        self.metadata
+            .name
+            .as_ref()
+            .map(|name| format!("{name}-{role}", role = HelloRole::Edge))
+    }
+
+    /// Metadata about a server rolegroup
+    pub fn server_rolegroup_ref(
+        &self,
+        group_name: impl Into<String>,
+    ) -> RoleGroupRef<HelloCluster> {
+        RoleGroupRef {
+            cluster: ObjectRef::from_obj(self),
+            role: HelloRole::Server.to_string(),
+            role_group: group_name.into(),
+        }
+    }
+
+    /// List all pods expected to form the cluster
+    ///
+    /// We try to predict the pods here rather than looking at the current cluster state in order to
+    /// avoid instance churn. Pods of role groups running as Deployment are left out, as their names
+    /// are not predictable.
+    pub fn pods(&self) -> Result<impl Iterator<Item = PodRef> + '_, NoNamespaceError> {
+        let ns = self.metadata.namespace.clone().context(NoNamespaceSnafu)?;
+        Ok(HelloRole::iter()
+            .flat_map(move |role| {
+                let role_groups = self
+                    .role(&role)
+                    .map(|any_role| any_role.role_groups())
+                    .unwrap_or_default();
+                role_groups
+                    .into_iter()
+                    .map(move |(rolegroup_name, replicas)| {
+                        (
+                            role.clone(),
+                            role.rolegroup_ref(self, rolegroup_name),
+                            replicas,
+                        )
+                    })
+            })
+            .filter(|(role, rolegroup_ref, _)| {
+                self.role_group_workload(role, rolegroup_ref) == HelloWorkload::StatefulSet
+            })
+            .flat_map(move |(_, rolegroup_ref, replicas)| {
+                let ns = ns.clone();
+                (0..replicas.unwrap_or(0)).map(move |i| PodRef {
+                    namespace: ns.clone(),
+                    role_group_service_name: rolegroup_ref.object_name(),
+                    pod_name: format!("{}-{}", rolegroup_ref.object_name(), i),
+                })
+            }))
+    }
+
+    /// The role config of the given role, if the role is defined
+    pub fn role_config(&self, role: &HelloRole) -> Option<&GenericRoleConfig> {
+        self.role(role).map(|any_role| any_role.role_config())
+    }
+
+    /// How the Pods of the given role group run. The reverse proxies of the edge role always run
+    /// as [`HelloWorkload::Deployment`].
+    pub fn role_group_workload(
+        &self,
+        role: &HelloRole,
+        rolegroup_ref: &RoleGroupRef<HelloCluster>,
+    ) -> HelloWorkload {
+        match role {
+            HelloRole::Server => self
+                .merged_config(role, rolegroup_ref)
+                .map_or(HelloWorkload::StatefulSet, |merged_config| {
+                    merged_config.workload
+                }),
+            HelloRole::Edge => HelloWorkload::Deployment,
+        }
+    }
+
+    /// Retrieve and merge the resource configs of the servers for role and role groups. Raises an
+    /// error for the edge role, whose config is merged by [`Self::merged_edge_config`].
+    pub fn merged_config(
+        &self,
+        role: &HelloRole,
+        rolegroup_ref: &RoleGroupRef<HelloCluster>,
+    ) -> Result<HelloConfig, Error> {
+        // Initialize the result with all default values as baseline
+        let conf_defaults = HelloConfig::default_config(self, role);
+
+        let role = match role {
+            HelloRole::Server => &self.spec.servers,
+            HelloRole::Edge => return NotAServerRoleSnafu { role: role.clone() }.fail(),
+        };
+        let mut conf_role = role.config.config.to_owned();
+
+        let role_group = self.role_group(rolegroup_ref)?;
+        let mut conf_role_group = role_group.config.config.to_owned();
+
+        // Merge more specific configs into default config
+        // Hierarchy is:
+        // 1. RoleGroup
+        // 2. Role
+        // 3. Default
+        conf_role.merge(&conf_defaults);
+        conf_role_group.merge(&conf_role);
+
+        tracing::debug!("Merged config: {:?}", conf_role_group);
+        fragment::validate(conf_role_group).context(FragmentValidationFailureSnafu)
+    }
+
+    /// Retrieve and merge the configs of the edge role and the given edge role group
+    pub fn merged_edge_config(
+        &self,
+        rolegroup_ref: &RoleGroupRef<HelloCluster>,
+    ) -> Result<EdgeConfig, Error> {
+        let conf_defaults = EdgeConfig::default_config(self);
+
+        let Some((role, role_group)) = self
+            .spec
+            .edges
+            .as_ref()
+            .map(|edges| &edges.role)
+            .and_then(|role| Some((role, role.role_groups.get(&rolegroup_ref.role_group)?)))
+        else {
+            return CannotRetrieveHelloRoleGroupSnafu {
+                role_group: rolegroup_ref.role_group.to_owned(),
+            }
+            .fail();
+        };
+        let mut conf_role = role.config.config.to_owned();
+        let mut conf_role_group = role_group.config.config.to_owned();
+
+        conf_role.merge(&conf_defaults);
+        conf_role_group.merge(&conf_role);
+
+        tracing::debug!("Merged edge config: {:?}", conf_role_group);
+        fragment::validate(conf_role_group).context(FragmentValidationFailureSnafu)
+    }
+}
+
+/// Reference to a single `Pod` that is a component of a [`HelloCluster`]
+/// Used for service discovery.
+pub struct PodRef {
+    pub namespace: String,
+    pub role_group_service_name: String,
+    pub pod_name: String,
+}
+
+impl PodRef {
+    pub fn fqdn(&self, cluster_info: &KubernetesClusterInfo) -> String {
+        format!(
+            "{pod_name}.{service_name}.{namespace}.svc.{cluster_domain}",
+            pod_name = self.pod_name,
+            service_name = self.role_group_service_name,
+            namespace = self.namespace,
+            cluster_domain = cluster_info.cluster_domain,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::cluster_defaults("{}", "{}", "Stackable", "blue")]
+    #[case::role_override("{greeting: {recipient: Role}}", "{}", "Role", "blue")]
+    #[case::role_group_override(
+        "{greeting: {recipient: Role}}",
+        "{greeting: {recipient: Group, color: red}}",
+        "Group",
+        "red"
+    )]
+    fn test_greeting_merge(
+        #[case] role_config: &str,
+        #[case] role_group_config: &str,
+        #[case] expected_recipient: &str,
+        #[case] expected_color: &str,
+    ) {
+        let input = format!(
+            r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+          servers:
+            config: {role_config}
+            roleGroups:
+              default:
+                replicas: 1
+                config: {role_group_config}
+        "#
+        );
+        let hello: HelloCluster = serde_yaml::from_str(&input).expect("illegal test input");
+        let role = HelloRole::Server;
+        let merged_config = hello
+            .merged_config(&role, &role.rolegroup_ref(&hello, "default"))
+            .unwrap();
+
+        assert_eq!(
+            merged_config.greeting,
+            GreetingConfig {
+                recipient: expected_recipient.to_owned(),
+                color: expected_color.to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_probes_merge() {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+          servers:
+            config:
+              probes:
+                liveness:
+                  initialDelaySeconds: 120
+            roleGroups:
+              default:
+                replicas: 1
+                config:
+                  probes:
+                    liveness:
+                      failureThreshold: 6
+        "#;
+        let hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+        let role = HelloRole::Server;
+        let merged_config = hello
+            .merged_config(&role, &role.rolegroup_ref(&hello, "default"))
+            .unwrap();
+
+        assert_eq!(
+            merged_config.probes,
+            ProbesConfig {
+                readiness: ProbeConfig {
+                    initial_delay_seconds: 10,
+                    period_seconds: 10,
+                    timeout_seconds: 1,
+                    failure_threshold: 5,
+                },
+                liveness: ProbeConfig {
+                    initial_delay_seconds: 120,
+                    period_seconds: 10,
+                    timeout_seconds: 1,
+                    failure_threshold: 6,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_edge_config_merge() {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+          servers:
+            roleGroups:
+              default:
+                replicas: 1
+          edges:
+            config:
+              cache:
+                maxSize: 128Mi
+            roleGroups:
+              default:
+                replicas: 2
+                config:
+                  cache:
+                    valid: 1m
+        "#;
+        let hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+        let role = HelloRole::Edge;
+        let merged_config = hello
+            .merged_edge_config(&role.rolegroup_ref(&hello, "default"))
+            .unwrap();
+
+        assert_eq!(
+            merged_config.cache,
+            EdgeCacheConfig {
+                valid: Duration::from_minutes_unchecked(1),
+                max_size: Quantity("128Mi".to_owned()),
+            }
+        );
+        assert!(matches!(
+            hello.merged_config(&role, &role.rolegroup_ref(&hello, "default")),
+            Err(Error::NotAServerRole { .. })
+        ));
+    }
+
+    #[test]
+    fn test_edge_image_defaults_to_the_stackable_registry() {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+          servers:
+            roleGroups:
+              default:
+                replicas: 1
+          edges:
+            roleGroups:
+              default:
+                replicas: 1
+        "#;
+        let hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+
+        let edge_image = hello
+            .spec
+            .edges
+            .unwrap()
+            .image
+            .resolve("nginx", "0.0.0-dev");
+
+        assert_eq!(edge_image.product_version, DEFAULT_EDGE_VERSION);
+        assert!(edge_image
+            .image
+            .ends_with("/nginx:1.27.3-stackable0.0.0-dev"));
+    }
+
+    #[test]
+    fn test_pods_of_deployments_are_not_listed() {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+          servers:
+            roleGroups:
+              stateful:
+                replicas: 2
+              stateless:
+                replicas: 2
+                config:
+                  workload: Deployment
+          edges:
+            roleGroups:
+              default:
+                replicas: 2
+        "#;
+        let hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+
+        let pod_names = hello
+            .pods()
+            .unwrap()
+            .map(|pod| pod.pod_name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            pod_names,
+            vec![
+                "hello-world-server-stateful-0",
+                "hello-world-server-stateful-1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scale_subresource_matches_scale_role_group() {
+        let crd = v1alpha2::HelloCluster::crd();
+        let scale = crd.spec.versions[0]
+            .subresources
+            .as_ref()
+            .and_then(|subresources| subresources.scale.as_ref())
+            .expect("the scale subresource must be defined");
+
+        assert_eq!(
+            scale.spec_replicas_path,
+            format!(
+                ".spec.servers.roleGroups.{}.replicas",
+                v1alpha2::SCALE_ROLE_GROUP
+            )
+        );
+    }
+}
@@ -0,0 +1,522 @@
+//! The first version of the `HelloCluster`, which is deprecated in favor of
+//! [`v1alpha2`](super::v1alpha2).
+//!
+//! The types in this module must not be changed anymore, as objects of this version are still
+//! served. Therefore this module has its own copies of all types, which were changed in later
+//! versions, and converts them explicitly from and to the current version. Only types, which are
+//! the same in all versions, e.g. [`GreetingConfig`], are shared.
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use stackable_operator::{
+    commons::{
+        affinity::StackableAffinity, authentication::ClientAuthenticationDetails,
+        cluster_operation::ClusterOperation, opa::OpaConfig, product_image_selection::ProductImage,
+        resources::Resources,
+    },
+    config::{fragment::Fragment, merge::Merge},
+    k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    kube::CustomResource,
+    product_logging::spec::Logging,
+    role_utils::{CommonConfiguration, GenericRoleConfig, JavaCommonConfig, Role},
+    schemars::{self, JsonSchema},
+    status::condition::ClusterCondition,
+    time::Duration,
+};
+
+use super::{
+    default_listener_class, v1alpha2, AutoscalingConfig, Container, GreetingConfig, HelloCanary,
+    HelloEdges, HelloGreeting, HelloIngress, HelloListenerAddress, HelloWorkload, JvmMemoryLimits,
+    PodManagementPolicy, ProbesConfigFragment, RollingUpdateConfigFragment, ServerStorageConfig,
+    DEFAULT_HTTP_PORT,
+};
+
+/// Keeps the fields of the v1alpha2 `HelloCluster`, which cannot be represented in v1alpha1, as
+/// JSON in the v1alpha1 object, so that they survive a round trip through v1alpha1.
+pub const V1ALPHA2_FIELDS_ANNOTATION: &str = "hello-world.stackable.tech/v1alpha2-fields";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to convert the {role} role"))]
+    ConvertRole {
+        source: serde_json::Error,
+        role: String,
+    },
+
+    #[snafu(display(
+        "failed to serialize the v1alpha2 fields into the {V1ALPHA2_FIELDS_ANNOTATION} annotation"
+    ))]
+    SerializeV1Alpha2Fields { source: serde_json::Error },
+}
+
+#[derive(Clone, CustomResource, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[kube(
+    group = "hello-world.stackable.tech",
+    version = "v1alpha1",
+    kind = "HelloCluster",
+    plural = "hello-world-clusters",
+    shortname = "hello-world",
+    status = "HelloClusterStatus",
+    derive = "PartialEq",
+    deprecated = "hello-world.stackable.tech/v1alpha1 HelloCluster is deprecated, use hello-world.stackable.tech/v1alpha2 instead",
+    namespaced,
+    crates(
+        kube_core = "stackable_operator::kube::core",
+        k8s_openapi = "stackable_operator::k8s_openapi",
+        schemars = "stackable_operator::schemars"
+    )
+)]
+pub struct HelloClusterSpec {
+    /// General Hello World cluster settings
+    pub cluster_config: HelloClusterConfig,
+    /// Cluster operations like pause reconciliation or cluster stop.
+    #[serde(default)]
+    pub cluster_operation: ClusterOperation,
+    /// The image to use. In this example this will be an nginx image
+    pub image: ProductImage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub servers: Option<Role<HelloConfigFragment, GenericRoleConfig, JavaCommonConfig>>,
+    pub recipient: String,
+    pub color: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloClusterConfig {
+    /// Authentication of clients requesting the greeting.
+    /// Currently a single [AuthenticationClass](https://docs.stackable.tech/home/stable/concepts/authentication)
+    /// using the `static`, `ldap` or `oidc` provider is supported.
+    #[serde(default)]
+    pub authentication: Vec<ClientAuthenticationDetails>,
+    /// Authorization of clients requesting the greeting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization: Option<HelloAuthorization>,
+    /// Name of the Vector aggregator discovery ConfigMap.
+    /// It must contain the key `ADDRESS` with the address of the Vector aggregator.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_aggregator_config_map_name: Option<String>,
+    /// This field controls which [ListenerClass](https://docs.stackable.tech/home/stable/listener-operator/listenerclass.html)
+    /// is used to expose the servers. The listener-operator provides the following ListenerClasses
+    /// out of the box, but any other ListenerClass present in the Kubernetes cluster can be used as well:
+    ///
+    /// * cluster-internal: Only reachable from within the Kubernetes cluster
+    ///
+    /// * external-unstable: Reachable from outside the Kubernetes cluster, the address may change
+    ///
+    /// * external-stable: Reachable from outside the Kubernetes cluster with a stable address
+    #[serde(default = "default_listener_class")]
+    pub listener_class: String,
+    /// TLS encryption settings for the servers
+    #[serde(default)]
+    pub tls: HelloTls,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloAuthorization {
+    /// Configure the OPA stacklet [discovery ConfigMap](https://docs.stackable.tech/home/stable/concepts/service_discovery)
+    /// and the name of the Rego package containing your authorization rules.
+    /// The package defaults to the name of the HelloCluster.
+    /// Requests are only allowed if the `allow` rule of the package evaluates to `true`.
+    /// The rule receives the input `{"user": ..., "method": ..., "path": ...}`, where the user is
+    /// `null` for anonymous requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opa: Option<OpaConfig>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloTls {
+    /// Name of the [SecretClass](https://docs.stackable.tech/home/stable/secret-operator/secretclass.html)
+    /// providing the certificate the servers use to authenticate themselves against clients.
+    /// If set, the greeting is only served via HTTPS, otherwise it is served via plain HTTP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_secret_class: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct HelloConfig {
+    #[fragment_attrs(serde(default))]
+    pub resources: Resources<ServerStorageConfig, JvmMemoryLimits>,
+    #[fragment_attrs(serde(default))]
+    pub logging: Logging<Container>,
+    #[fragment_attrs(serde(default))]
+    pub affinity: StackableAffinity,
+    /// Time period Pods have to gracefully shut down, e.g. `30m`, `1h` or `2d`. Consult the operator documentation for details.
+    #[fragment_attrs(serde(default))]
+    pub graceful_shutdown_timeout: Option<Duration>,
+    /// Overrides of the greeting, e.g. to greet different audiences per role group.
+    #[fragment_attrs(serde(default))]
+    pub greeting: GreetingConfig,
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloClusterStatus {
+    pub conditions: Vec<ClusterCondition>,
+    /// The addresses under which the roles are reachable, as resolved by the listener-operator.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listener_addresses: Vec<HelloListenerAddress>,
+}
+
+/// The fields of the v1alpha2 `HelloCluster`, which cannot be represented in v1alpha1. Fields with
+/// their default value are omitted.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct V1Alpha2Fields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    http_port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    canary: Option<HelloCanary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    progress_deadline: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    edges: Option<HelloEdges>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ingress: Option<HelloIngress>,
+    #[serde(default, skip_serializing_if = "V1Alpha2RoleFields::is_empty")]
+    servers: V1Alpha2RoleFields,
+}
+
+/// The fields of the v1alpha2 server configs, which cannot be represented in v1alpha1, of the role
+/// and of its role groups
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct V1Alpha2RoleFields {
+    #[serde(default, skip_serializing_if = "V1Alpha2ConfigFields::is_empty")]
+    config: V1Alpha2ConfigFields,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    role_groups: BTreeMap<String, V1Alpha2ConfigFields>,
+}
+
+impl V1Alpha2RoleFields {
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// The fields of a v1alpha2 server config, which cannot be represented in v1alpha1. Fields with
+/// their default value are omitted.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct V1Alpha2ConfigFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    workload: Option<HelloWorkload>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pod_management_policy: Option<PodManagementPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rolling_update: Option<RollingUpdateConfigFragment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    probes: Option<ProbesConfigFragment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    autoscaling: Option<AutoscalingConfig>,
+}
+
+impl V1Alpha2ConfigFields {
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl V1Alpha2Fields {
+    /// Removes the fields from the annotations of a v1alpha1 object
+    fn take(metadata: &mut ObjectMeta) -> Self {
+        let Some(annotations) = &mut metadata.annotations else {
+            return Self::default();
+        };
+        let fields = annotations.remove(V1ALPHA2_FIELDS_ANNOTATION);
+        if annotations.is_empty() {
+            metadata.annotations = None;
+        }
+
+        fields
+            .map(|fields| {
+                serde_json::from_str(&fields).unwrap_or_else(|error| {
+                    tracing::warn!(
+                        %error,
+                        annotation = V1ALPHA2_FIELDS_ANNOTATION,
+                        "ignoring the invalid annotation, the v1alpha2 fields get their defaults"
+                    );
+                    Self::default()
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    /// Adds the fields to the annotations of a v1alpha1 object, unless all of them are defaults
+    fn put(self, metadata: &mut ObjectMeta) -> Result<(), Error> {
+        if self == Self::default() {
+            return Ok(());
+        }
+        let fields = serde_json::to_string(&self).context(SerializeV1Alpha2FieldsSnafu)?;
+        metadata
+            .annotations
+            .get_or_insert_with(Default::default)
+            .insert(V1ALPHA2_FIELDS_ANNOTATION.to_owned(), fields);
+        Ok(())
+    }
+}
+
+impl TryFrom<HelloCluster> for v1alpha2::HelloCluster {
+    type Error = Error;
+
+    /// A missing `servers` role is converted into a role without role groups, which results in
+    /// the same (empty) cluster. Fields which were added in later versions are restored from the
+    /// [`V1ALPHA2_FIELDS_ANNOTATION`] or get their defaults.
+    fn try_from(hello: HelloCluster) -> Result<Self, Error> {
+        let mut metadata = hello.metadata;
+        let v1alpha2_fields = V1Alpha2Fields::take(&mut metadata);
+        let HelloClusterSpec {
+            cluster_config,
+            cluster_operation,
+            image,
+            servers,
+            recipient,
+            color,
+        } = hello.spec;
+
+        let mut server_fields = v1alpha2_fields.servers;
+        let servers = convert_role(servers.unwrap_or_else(empty_role), |role_group, config| {
+            let fields = match role_group {
+                Some(role_group) => server_fields
+                    .role_groups
+                    .remove(role_group)
+                    .unwrap_or_default(),
+                None => std::mem::take(&mut server_fields.config),
+            };
+            config.into_v1alpha2(fields)
+        })
+        .context(ConvertRoleSnafu { role: "servers" })?;
+
+        Ok(Self {
+            metadata,
+            spec: v1alpha2::HelloClusterSpec {
+                cluster_config: super::HelloClusterConfig {
+                    greeting: HelloGreeting { recipient, color },
+                    authentication: cluster_config.authentication,
+                    authorization: cluster_config.authorization.map(|authorization| {
+                        super::HelloAuthorization {
+                            opa: authorization.opa,
+                        }
+                    }),
+                    vector_aggregator_config_map_name: cluster_config
+                        .vector_aggregator_config_map_name,
+                    listener_class: cluster_config.listener_class,
+                    tls: super::HelloTls {
+                        server_secret_class: cluster_config.tls.server_secret_class,
+                    },
+                    http_port: v1alpha2_fields.http_port.unwrap_or(DEFAULT_HTTP_PORT),
+                    canary: v1alpha2_fields.canary,
+                    progress_deadline: v1alpha2_fields.progress_deadline,
+                    ingress: v1alpha2_fields.ingress,
+                },
+                cluster_operation,
+                image,
+                servers,
+                edges: v1alpha2_fields.edges,
+            },
+            status: hello.status.map(|status| super::HelloClusterStatus {
+                conditions: status.conditions,
+                listener_addresses: status.listener_addresses,
+                ..Default::default()
+            }),
+        })
+    }
+}
+
+impl TryFrom<v1alpha2::HelloCluster> for HelloCluster {
+    type Error = Error;
+
+    /// Fields which cannot be represented in v1alpha1 are kept in the
+    /// [`V1ALPHA2_FIELDS_ANNOTATION`]. A `servers` role without role groups and configuration is
+    /// converted back into a missing role.
+    ///
+    /// The status only keeps the fields which exist in v1alpha1. This loses nothing, because the
+    /// status is only written by the operator via the v1alpha2 status subresource, and the API
+    /// server keeps the stored status when a v1alpha1 object is written.
+    fn try_from(hello: v1alpha2::HelloCluster) -> Result<Self, Error> {
+        let v1alpha2::HelloClusterSpec {
+            cluster_config,
+            cluster_operation,
+            image,
+            servers,
+            edges,
+        } = hello.spec;
+
+        let mut server_fields = V1Alpha2RoleFields::default();
+        let servers = convert_role(servers, |role_group, config| {
+            let (config, fields) = HelloConfigFragment::from_v1alpha2(config);
+            match role_group {
+                Some(role_group) => {
+                    if !fields.is_empty() {
+                        server_fields
+                            .role_groups
+                            .insert(role_group.to_owned(), fields);
+                    }
+                }
+                None => server_fields.config = fields,
+            }
+            config
+        })
+        .context(ConvertRoleSnafu { role: "servers" })?;
+
+        let mut metadata = hello.metadata;
+        V1Alpha2Fields {
+            http_port: (cluster_config.http_port != DEFAULT_HTTP_PORT)
+                .then_some(cluster_config.http_port),
+            canary: cluster_config.canary,
+            progress_deadline: cluster_config.progress_deadline,
+            edges,
+            ingress: cluster_config.ingress,
+            servers: server_fields,
+        }
+        .put(&mut metadata)?;
+
+        Ok(Self {
+            metadata,
+            spec: HelloClusterSpec {
+                cluster_config: HelloClusterConfig {
+                    authentication: cluster_config.authentication,
+                    authorization: cluster_config.authorization.map(|authorization| {
+                        HelloAuthorization {
+                            opa: authorization.opa,
+                        }
+                    }),
+                    vector_aggregator_config_map_name: cluster_config
+                        .vector_aggregator_config_map_name,
+                    listener_class: cluster_config.listener_class,
+                    tls: HelloTls {
+                        server_secret_class: cluster_config.tls.server_secret_class,
+                    },
+                },
+                cluster_operation,
+                image,
+                servers: (servers != empty_role()).then_some(servers),
+                recipient: cluster_config.greeting.recipient,
+                color: cluster_config.greeting.color,
+            },
+            status: hello.status.map(|status| HelloClusterStatus {
+                conditions: status.conditions,
+                listener_addresses: status.listener_addresses,
+            }),
+        })
+    }
+}
+
+impl HelloConfigFragment {
+    /// Converts the config into v1alpha2 and restores the fields, which cannot be represented in
+    /// v1alpha1
+    fn into_v1alpha2(self, fields: V1Alpha2ConfigFields) -> super::HelloConfigFragment {
+        let Self {
+            resources,
+            logging,
+            affinity,
+            graceful_shutdown_timeout,
+            greeting,
+        } = self;
+
+        super::HelloConfigFragment {
+            resources,
+            logging,
+            affinity,
+            graceful_shutdown_timeout,
+            greeting,
+            workload: fields.workload,
+            pod_management_policy: fields.pod_management_policy,
+            rolling_update: fields.rolling_update.unwrap_or_default(),
+            probes: fields.probes.unwrap_or_default(),
+            autoscaling: fields.autoscaling,
+        }
+    }
+
+    /// Converts the config from v1alpha2 and returns the fields, which cannot be represented in
+    /// v1alpha1, alongside
+    fn from_v1alpha2(config: super::HelloConfigFragment) -> (Self, V1Alpha2ConfigFields) {
+        let super::HelloConfigFragment {
+            resources,
+            logging,
+            affinity,
+            graceful_shutdown_timeout,
+            greeting,
+            workload,
+            pod_management_policy,
+            rolling_update,
+            probes,
+            autoscaling,
+        } = config;
+
+        let config = Self {
+            resources,
+            logging,
+            affinity,
+            graceful_shutdown_timeout,
+            greeting,
+        };
+        let fields = V1Alpha2ConfigFields {
+            workload,
+            pod_management_policy,
+            rolling_update: (rolling_update != RollingUpdateConfigFragment::default())
+                .then_some(rolling_update),
+            probes: (probes != ProbesConfigFragment::default()).then_some(probes),
+            autoscaling,
+        };
+        (config, fields)
+    }
+}
+
+/// The role, which a missing v1alpha1 `servers` role corresponds to
+fn empty_role<T: Default>() -> Role<T, GenericRoleConfig, JavaCommonConfig> {
+    Role {
+        config: CommonConfiguration::default(),
+        role_config: GenericRoleConfig::default(),
+        role_groups: HashMap::new(),
+    }
+}
+
+/// Converts the configs of a role and of its role groups with `convert_config`, which receives
+/// the name of the role group or `None` for the role.
+fn convert_role<Source, Target>(
+    mut role: Role<Source, GenericRoleConfig, JavaCommonConfig>,
+    mut convert_config: impl FnMut(Option<&str>, Source) -> Target,
+) -> Result<Role<Target, GenericRoleConfig, JavaCommonConfig>, serde_json::Error>
+where
+    Source: Default + Serialize,
+    Target: Default + DeserializeOwned,
+{
+    let role_config = convert_config(None, std::mem::take(&mut role.config.config));
+    let mut role_group_configs = role
+        .role_groups
+        .iter_mut()
+        .map(|(name, role_group)| {
+            let config = convert_config(Some(name), std::mem::take(&mut role_group.config.config));
+            (name.clone(), config)
+        })
+        .collect::<HashMap<_, _>>();
+
+    // The configs were taken out and the rest of the role is the same in all versions, so it is
+    // carried over via its JSON representation.
+    let mut converted: Role<Target, GenericRoleConfig, JavaCommonConfig> =
+        serde_json::from_value(serde_json::to_value(role)?)?;
+    converted.config.config = role_config;
+    for (name, role_group) in &mut converted.role_groups {
+        if let Some(config) = role_group_configs.remove(name) {
+            role_group.config.config = config;
+        }
+    }
+    Ok(converted)
+}
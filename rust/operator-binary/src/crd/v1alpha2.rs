@@ -0,0 +1,63 @@
+//! The current version of the `HelloCluster`, which the operator reconciles.
+//!
+//! Compared to [`v1alpha1`](super::v1alpha1), the greeting moved from the top level into
+//! `clusterConfig.greeting` and the `servers` role is mandatory.
+use serde::{Deserialize, Serialize};
+use stackable_operator::{
+    commons::{cluster_operation::ClusterOperation, product_image_selection::ProductImage},
+    kube::CustomResource,
+    role_utils::{GenericRoleConfig, JavaCommonConfig, Role},
+    schemars::{self, JsonSchema},
+};
+
+use super::{HelloClusterConfig, HelloClusterStatus, HelloConfigFragment, HelloEdges};
+
+pub const VERSION: &str = "v1alpha2";
+
+/// The role group which is scaled by the `scale` subresource.
+///
+/// The paths of the subresource are fixed, so only clusters consisting of this single role group
+/// can be scaled with `kubectl scale` or an autoscaler. Other scale requests are rejected by
+/// [`validate_scale`](crate::webhook::validation::validate_scale).
+pub const SCALE_ROLE_GROUP: &str = "default";
+
+#[derive(Clone, CustomResource, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[kube(
+    group = "hello-world.stackable.tech",
+    version = "v1alpha2",
+    kind = "HelloCluster",
+    plural = "hello-world-clusters",
+    shortname = "hello-world",
+    status = "HelloClusterStatus",
+    derive = "PartialEq",
+    printcolumn = r#"{"name":"Version","type":"string","jsonPath":".status.productVersion"}"#,
+    printcolumn = r#"{"name":"Recipient","type":"string","jsonPath":".spec.clusterConfig.greeting.recipient"}"#,
+    printcolumn = r#"{"name":"Listener Class","type":"string","jsonPath":".spec.clusterConfig.listenerClass"}"#,
+    printcolumn = r#"{"name":"Available","type":"string","jsonPath":".status.conditions[?(@.type==\"Available\")].status"}"#,
+    printcolumn = r#"{"name":"Age","type":"date","jsonPath":".metadata.creationTimestamp"}"#,
+    scale = r#"{"specReplicasPath":".spec.servers.roleGroups.default.replicas","statusReplicasPath":".status.replicas","labelSelectorPath":".status.selector"}"#,
+    namespaced,
+    crates(
+        kube_core = "stackable_operator::kube::core",
+        k8s_openapi = "stackable_operator::k8s_openapi",
+        schemars = "stackable_operator::schemars"
+    )
+)]
+pub struct HelloClusterSpec {
+    /// General Hello World cluster settings
+    pub cluster_config: HelloClusterConfig,
+    /// Cluster operations like pause reconciliation or cluster stop.
+    #[serde(default)]
+    pub cluster_operation: ClusterOperation,
+    /// The image to use. In this example this will be an nginx image
+    pub image: ProductImage,
+    /// The servers serving the greeting.
+    /// Only servers consisting of the single role group `default` can be scaled via the `scale`
+    /// subresource, e.g. with `kubectl scale`.
+    pub servers: Role<HelloConfigFragment, GenericRoleConfig, JavaCommonConfig>,
+    /// Reverse proxies in front of the servers, which cache the greeting.
+    /// They are reachable via the `<cluster name>-edge` Service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edges: Option<HelloEdges>,
+}
@@ -0,0 +1,125 @@
+//! Reports the problems of a HelloCluster, which only the user can fix, in the `Degraded`
+//! condition.
+//!
+//! The condition types of a cluster are fixed, so an invalid greeting and a failed rollout share
+//! the `Degraded` condition. Instead of merging two conditions of the same type, one condition is
+//! built from both problems, so that neither hides the other: the reasons of all present problems
+//! are joined by `And`, e.g. `InvalidGreetingAndRolloutFailed`, and so are their messages.
+use stackable_operator::status::condition::{
+    ClusterCondition, ClusterConditionSet, ClusterConditionStatus, ClusterConditionType,
+    ConditionBuilder,
+};
+
+const INVALID_GREETING_REASON: &str = "InvalidGreeting";
+const ROLLOUT_FAILED_REASON: &str = "RolloutFailed";
+
+pub struct DegradedConditionBuilder {
+    /// Why the greeting of a role group is invalid
+    pub invalid_greeting: Option<String>,
+    /// Why the role groups were rolled back to the last known good spec
+    pub rollout_failed: Option<String>,
+}
+
+impl ConditionBuilder for DegradedConditionBuilder {
+    fn build_conditions(&self) -> ClusterConditionSet {
+        let problems = [
+            (INVALID_GREETING_REASON, &self.invalid_greeting),
+            (ROLLOUT_FAILED_REASON, &self.rollout_failed),
+        ]
+        .into_iter()
+        .filter_map(|(reason, message)| Some((reason, message.as_deref()?)))
+        .collect::<Vec<_>>();
+
+        let condition = if problems.is_empty() {
+            ClusterCondition {
+                reason: None,
+                message: Some("The greeting is valid and no rollout failed".to_string()),
+                status: ClusterConditionStatus::False,
+                type_: ClusterConditionType::Degraded,
+                last_transition_time: None,
+                last_update_time: None,
+            }
+        } else {
+            ClusterCondition {
+                reason: Some(
+                    problems
+                        .iter()
+                        .map(|(reason, _)| *reason)
+                        .collect::<Vec<_>>()
+                        .join("And"),
+                ),
+                message: Some(
+                    problems
+                        .iter()
+                        .map(|(_, message)| *message)
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                ),
+                status: ClusterConditionStatus::True,
+                type_: ClusterConditionType::Degraded,
+                last_transition_time: None,
+                last_update_time: None,
+            }
+        };
+
+        vec![condition].into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::healthy(
+        None,
+        None,
+        ClusterConditionStatus::False,
+        None,
+        "The greeting is valid and no rollout failed"
+    )]
+    #[case::invalid_greeting(
+        Some("invalid greeting"),
+        None,
+        ClusterConditionStatus::True,
+        Some("InvalidGreeting"),
+        "invalid greeting"
+    )]
+    #[case::rollout_failed(
+        None,
+        Some("rolled back"),
+        ClusterConditionStatus::True,
+        Some("RolloutFailed"),
+        "rolled back"
+    )]
+    #[case::both(
+        Some("invalid greeting"),
+        Some("rolled back"),
+        ClusterConditionStatus::True,
+        Some("InvalidGreetingAndRolloutFailed"),
+        "invalid greeting; rolled back"
+    )]
+    fn test_degraded_condition(
+        #[case] invalid_greeting: Option<&str>,
+        #[case] rollout_failed: Option<&str>,
+        #[case] expected_status: ClusterConditionStatus,
+        #[case] expected_reason: Option<&str>,
+        #[case] expected_message: &str,
+    ) {
+        let builder = DegradedConditionBuilder {
+            invalid_greeting: invalid_greeting.map(str::to_owned),
+            rollout_failed: rollout_failed.map(str::to_owned),
+        };
+
+        let conditions: Vec<ClusterCondition> = builder.build_conditions().into();
+
+        assert_eq!(conditions.len(), 1);
+        let condition = &conditions[0];
+        assert_eq!(condition.type_, ClusterConditionType::Degraded);
+        assert_eq!(condition.status, expected_status);
+        assert_eq!(condition.reason.as_deref(), expected_reason);
+        assert_eq!(condition.message.as_deref(), Some(expected_message));
+    }
+}
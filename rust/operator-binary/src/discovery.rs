@@ -0,0 +1,266 @@
+//! Builds the discovery [`ConfigMap`], which allows other workloads to find the greeting endpoint
+//! of a [`HelloCluster`] without hard-coding Service names.
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_operator::{
+    builder::{configmap::ConfigMapBuilder, meta::ObjectMetaBuilder},
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::api::core::v1::ConfigMap,
+    kube::ResourceExt,
+    utils::cluster_info::KubernetesClusterInfo,
+};
+
+use crate::{
+    controller::build_recommended_labels,
+    crd::{HelloCluster, HelloRole, NoNamespaceError},
+};
+
+pub const DISCOVERY_URL_KEY: &str = "HELLO_WORLD_URL";
+pub const DISCOVERY_PODS_KEY: &str = "HELLO_WORLD_PODS";
+pub const DISCOVERY_PORT_KEY: &str = "HELLO_WORLD_PORT";
+pub const DISCOVERY_EDGE_URL_KEY: &str = "HELLO_WORLD_EDGE_URL";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object has no namespace"))]
+    ObjectHasNoNamespace,
+
+    #[snafu(display("failed to list the expected Pods"))]
+    ExpectedPods { source: NoNamespaceError },
+
+    #[snafu(display("failed to calculate the role service name"))]
+    RoleServiceNameNotFound,
+
+    #[snafu(display("object is missing metadata to build owner reference"))]
+    ObjectMissingMetadataForOwnerRef {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build Metadata"))]
+    MetadataBuild {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build ConfigMap"))]
+    BuildConfigMap {
+        source: stackable_operator::builder::configmap::Error,
+    },
+}
+
+/// The URL of the role Service, which load-balances over all servers
+pub fn role_service_url(
+    hello: &HelloCluster,
+    cluster_info: &KubernetesClusterInfo,
+) -> Result<String, Error> {
+    let namespace = hello.namespace().context(ObjectHasNoNamespaceSnafu)?;
+    let role_service_name = hello
+        .server_role_service_name()
+        .context(RoleServiceNameNotFoundSnafu)?;
+    Ok(format!(
+        "{scheme}://{role_service_name}.{namespace}.svc.{cluster_domain}:{http_port}",
+        cluster_domain = cluster_info.cluster_domain,
+        scheme = scheme(hello),
+        http_port = hello.http_port(),
+    ))
+}
+
+/// The servers and the reverse proxies serve HTTPS, if the servers use TLS
+fn scheme(hello: &HelloCluster) -> &'static str {
+    if hello.server_secret_class().is_some() {
+        "https"
+    } else {
+        "http"
+    }
+}
+
+/// The URL of the edge role Service, which load-balances over the caching reverse proxies, if
+/// the edge role is defined
+pub fn edge_role_service_url(
+    hello: &HelloCluster,
+    cluster_info: &KubernetesClusterInfo,
+) -> Result<Option<String>, Error> {
+    let namespace = hello.namespace().context(ObjectHasNoNamespaceSnafu)?;
+
+    let edge_role_service_name = hello
+        .spec
+        .edges
+        .as_ref()
+        .and_then(|_| hello.edge_role_service_name());
+
+    Ok(edge_role_service_name.map(|edge_role_service_name| {
+        format!(
+            "{scheme}://{edge_role_service_name}.{namespace}.svc.{cluster_domain}:{http_port}",
+            scheme = scheme(hello),
+            cluster_domain = cluster_info.cluster_domain,
+            http_port = hello.http_port(),
+        )
+    }))
+}
+
+/// The discovery [`ConfigMap`] is named after the [`HelloCluster`] and contains
+///
+/// * the URL of the role Service, which load-balances over all servers,
+/// * the addresses of the individual server Pods,
+/// * the port serving the greeting and
+/// * the URL of the edge role Service, if the edge role is defined.
+pub fn build_discovery_configmap(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    cluster_info: &KubernetesClusterInfo,
+) -> Result<ConfigMap, Error> {
+    let pods = hello
+        .pods()
+        .context(ExpectedPodsSnafu)?
+        .map(|pod| {
+            format!(
+                "{fqdn}:{http_port}",
+                fqdn = pod.fqdn(cluster_info),
+                http_port = hello.http_port(),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut config_map = ConfigMapBuilder::new();
+    config_map
+        .metadata(
+            ObjectMetaBuilder::new()
+                .name_and_namespace(hello)
+                .name(hello.name_any())
+                .ownerreference_from_resource(hello, None, Some(true))
+                .context(ObjectMissingMetadataForOwnerRefSnafu)?
+                .with_recommended_labels(build_recommended_labels(
+                    hello,
+                    &resolved_product_image.app_version_label,
+                    &HelloRole::Server.to_string(),
+                    "discovery",
+                ))
+                .context(MetadataBuildSnafu)?
+                .build(),
+        )
+        .add_data(DISCOVERY_URL_KEY, role_service_url(hello, cluster_info)?)
+        .add_data(DISCOVERY_PODS_KEY, pods)
+        .add_data(DISCOVERY_PORT_KEY, hello.http_port().to_string());
+    if let Some(edge_url) = edge_role_service_url(hello, cluster_info)? {
+        config_map.add_data(DISCOVERY_EDGE_URL_KEY, edge_url);
+    }
+
+    config_map.build().context(BuildConfigMapSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    fn hello(tls: &str, edges: &str) -> HelloCluster {
+        let input = format!(
+            r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+          uid: 3f1b8d5e-2a57-4c0e-9d64-0a8f1e7b6c21
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+            tls: {tls}
+            httpPort: 9090
+          servers:
+            roleGroups:
+              default:
+                replicas: 2
+              stateless:
+                replicas: 1
+                config:
+                  workload: Deployment
+          edges: {edges}
+        "#
+        );
+        serde_yaml::from_str(&input).expect("illegal test input")
+    }
+
+    fn cluster_info() -> KubernetesClusterInfo {
+        KubernetesClusterInfo {
+            cluster_domain: "cluster.local".parse().expect("illegal test input"),
+        }
+    }
+
+    #[rstest]
+    #[case::http("{}", "http://hello-world.default.svc.cluster.local:9090")]
+    #[case::https(
+        "{serverSecretClass: tls}",
+        "https://hello-world.default.svc.cluster.local:9090"
+    )]
+    fn test_role_service_url(#[case] tls: &str, #[case] expected_url: &str) {
+        let hello = hello(tls, "null");
+
+        assert_eq!(
+            role_service_url(&hello, &cluster_info()).unwrap(),
+            expected_url
+        );
+    }
+
+    #[test]
+    fn test_role_service_url_needs_a_namespace() {
+        let mut hello = hello("{}", "null");
+        hello.metadata.namespace = None;
+
+        assert!(matches!(
+            role_service_url(&hello, &cluster_info()),
+            Err(Error::ObjectHasNoNamespace)
+        ));
+    }
+
+    #[test]
+    fn test_discovery_configmap() {
+        let hello = hello("{}", "null");
+        let resolved_product_image = hello
+            .spec
+            .image
+            .resolve("hello", crate::built_info::PKG_VERSION);
+
+        let config_map =
+            build_discovery_configmap(&hello, &resolved_product_image, &cluster_info()).unwrap();
+
+        assert_eq!(config_map.name_any(), "hello-world");
+        let data = config_map.data.unwrap();
+        assert_eq!(
+            data[DISCOVERY_URL_KEY],
+            "http://hello-world.default.svc.cluster.local:9090"
+        );
+        // The Pods of the Deployment are not listed, as their names are not predictable
+        assert_eq!(
+            data[DISCOVERY_PODS_KEY],
+            "hello-world-server-default-0.hello-world-server-default.default.svc.cluster.local:9090,\
+             hello-world-server-default-1.hello-world-server-default.default.svc.cluster.local:9090"
+        );
+        assert_eq!(data[DISCOVERY_PORT_KEY], "9090");
+        assert!(!data.contains_key(DISCOVERY_EDGE_URL_KEY));
+    }
+
+    #[test]
+    fn test_discovery_configmap_contains_the_edge_url() {
+        let hello = hello(
+            "{serverSecretClass: tls}",
+            "{roleGroups: {default: {replicas: 1}}}",
+        );
+        let resolved_product_image = hello
+            .spec
+            .image
+            .resolve("hello", crate::built_info::PKG_VERSION);
+
+        let config_map =
+            build_discovery_configmap(&hello, &resolved_product_image, &cluster_info()).unwrap();
+
+        assert_eq!(
+            config_map.data.unwrap()[DISCOVERY_EDGE_URL_KEY],
+            "https://hello-world-edge.default.svc.cluster.local:9090"
+        );
+    }
+}
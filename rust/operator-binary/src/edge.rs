@@ -0,0 +1,646 @@
+//! The edge role runs nginx reverse proxies in front of the servers, which cache the greeting.
+//!
+//! The reverse proxies forward all requests to the role [`Service`] of the servers. They keep no
+//! state besides their cache, so every edge role group runs as [`Deployment`].
+//!
+//! If the servers use TLS, the reverse proxies terminate TLS with a certificate of the same
+//! SecretClass, so that credentials are never sent in plain text.
+use std::collections::BTreeMap;
+
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_operator::{
+    builder::{
+        configmap::ConfigMapBuilder,
+        meta::ObjectMetaBuilder,
+        pod::{
+            container::ContainerBuilder,
+            volume::{SecretFormat, SecretOperatorVolumeSourceBuilder, VolumeBuilder},
+            PodBuilder,
+        },
+    },
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::{
+        api::{
+            apps::v1::{Deployment, DeploymentSpec},
+            core::v1::{
+                ConfigMap, ConfigMapVolumeSource, EmptyDirVolumeSource, HTTPGetAction, Probe,
+                Service, ServicePort, ServiceSpec, Volume,
+            },
+        },
+        apimachinery::pkg::{apis::meta::v1::LabelSelector, util::intstr::IntOrString},
+        DeepMerge,
+    },
+    kvp::{Annotation, Labels},
+    memory::{BinaryMultiple, MemoryQuantity},
+    role_utils::RoleGroupRef,
+    utils::cluster_info::KubernetesClusterInfo,
+};
+
+use crate::{
+    controller::{build_recommended_labels, CONFIG_HASH_ANNOTATION},
+    crd::{
+        EdgeCacheConfig, EdgeConfig, HelloCluster, HelloRole, APP_NAME, NGINX_CONF,
+        STACKABLE_CACHE_DIR, STACKABLE_CACHE_DIR_NAME, STACKABLE_CONFIG_DIR,
+        STACKABLE_CONFIG_DIR_NAME,
+    },
+    discovery::role_service_url,
+    operations::graceful_shutdown::{self, add_graceful_shutdown_config},
+};
+
+/// nginx is not part of the product image, so the reverse proxies run their own image, see
+/// `edges.image`. It stops gracefully on `SIGQUIT`, which it declares as its stop signal.
+const EDGE_IMAGE_BASE_NAME: &str = "nginx";
+const EDGE_CONTAINER_NAME: &str = "nginx";
+const HEALTH_PATH: &str = "/healthz";
+const STACKABLE_TLS_DIR: &str = "/stackable/tls";
+const STACKABLE_TLS_DIR_NAME: &str = "tls";
+/// nginx only evicts cache entries periodically, so some room is left in the cache volume
+const CACHE_VOLUME_USAGE: f32 = 0.9;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object is missing metadata to build owner reference"))]
+    ObjectMissingMetadataForOwnerRef {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build Metadata"))]
+    MetadataBuild {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build label"))]
+    LabelBuild {
+        source: stackable_operator::kvp::LabelError,
+    },
+
+    #[snafu(display("failed to build Annotation"))]
+    AnnotationBuild {
+        source: stackable_operator::kvp::AnnotationError,
+    },
+
+    #[snafu(display("failed to calculate the edge role service name"))]
+    EdgeRoleServiceNameNotFound,
+
+    #[snafu(display("the edge role group {rolegroup} is not defined"))]
+    RoleGroupNotDefined {
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to build the URL of the servers"))]
+    BuildUpstreamUrl { source: crate::discovery::Error },
+
+    #[snafu(display("the cache.maxSize of {rolegroup} is invalid"))]
+    InvalidCacheMaxSize {
+        source: stackable_operator::memory::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to build ConfigMap for {rolegroup}"))]
+    BuildConfigMap {
+        source: stackable_operator::builder::configmap::Error,
+        rolegroup: RoleGroupRef<HelloCluster>,
+    },
+
+    #[snafu(display("failed to create the nginx container"))]
+    CreateContainer {
+        source: stackable_operator::builder::pod::container::Error,
+    },
+
+    #[snafu(display("failed to add needed volume"))]
+    AddVolume {
+        source: stackable_operator::builder::pod::Error,
+    },
+
+    #[snafu(display("failed to add needed volumeMount"))]
+    AddVolumeMount {
+        source: stackable_operator::builder::pod::container::Error,
+    },
+
+    #[snafu(display("failed to build the TLS CA SecretClass Volume"))]
+    TlsCaSecretClassVolumeBuild {
+        source: stackable_operator::builder::pod::volume::SecretOperatorVolumeSourceBuilderError,
+    },
+
+    #[snafu(display("failed to configure graceful shutdown"))]
+    GracefulShutdown { source: graceful_shutdown::Error },
+}
+
+/// The edge role [`Service`] load-balances over all reverse proxies. Clients should use it
+/// instead of the role Service of the servers to benefit from the cache.
+pub fn build_edge_role_service(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+) -> Result<Service, Error> {
+    let role_name = HelloRole::Edge.to_string();
+
+    Ok(Service {
+        metadata: ObjectMetaBuilder::new()
+            .name_and_namespace(hello)
+            .name(
+                hello
+                    .edge_role_service_name()
+                    .context(EdgeRoleServiceNameNotFoundSnafu)?,
+            )
+            .ownerreference_from_resource(hello, None, Some(true))
+            .context(ObjectMissingMetadataForOwnerRefSnafu)?
+            .with_recommended_labels(build_recommended_labels(
+                hello,
+                &resolved_product_image.app_version_label,
+                &role_name,
+                "global",
+            ))
+            .context(MetadataBuildSnafu)?
+            .build(),
+        spec: Some(ServiceSpec {
+            type_: Some("ClusterIP".to_string()),
+            ports: Some(service_ports(hello)),
+            selector: Some(
+                Labels::role_selector(hello, APP_NAME, &role_name)
+                    .context(LabelBuildSnafu)?
+                    .into(),
+            ),
+            ..ServiceSpec::default()
+        }),
+        status: None,
+    })
+}
+
+/// The headless rolegroup [`Service`] allows direct access to the reverse proxies of a role group
+pub fn build_edge_rolegroup_service(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    rolegroup: &RoleGroupRef<HelloCluster>,
+) -> Result<Service, Error> {
+    Ok(Service {
+        metadata: ObjectMetaBuilder::new()
+            .name_and_namespace(hello)
+            .name(rolegroup.object_name())
+            .ownerreference_from_resource(hello, None, Some(true))
+            .context(ObjectMissingMetadataForOwnerRefSnafu)?
+            .with_recommended_labels(build_recommended_labels(
+                hello,
+                &resolved_product_image.app_version_label,
+                &rolegroup.role,
+                &rolegroup.role_group,
+            ))
+            .context(MetadataBuildSnafu)?
+            .build(),
+        spec: Some(ServiceSpec {
+            type_: Some("ClusterIP".to_string()),
+            cluster_ip: Some("None".to_string()),
+            ports: Some(service_ports(hello)),
+            selector: Some(
+                Labels::role_group_selector(
+                    hello,
+                    APP_NAME,
+                    &rolegroup.role,
+                    &rolegroup.role_group,
+                )
+                .context(LabelBuildSnafu)?
+                .into(),
+            ),
+            ..ServiceSpec::default()
+        }),
+        status: None,
+    })
+}
+
+/// The `cache.maxSize` of the role group, which must be given in binary units, e.g. `64Mi`
+pub fn cache_max_size(
+    rolegroup: &RoleGroupRef<HelloCluster>,
+    merged_config: &EdgeConfig,
+) -> Result<MemoryQuantity, Error> {
+    MemoryQuantity::try_from(&merged_config.cache.max_size).context(InvalidCacheMaxSizeSnafu {
+        rolegroup: rolegroup.clone(),
+    })
+}
+
+/// The rolegroup [`ConfigMap`] contains the `nginx.conf` of the reverse proxies
+pub fn build_edge_rolegroup_config_map(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    rolegroup: &RoleGroupRef<HelloCluster>,
+    merged_config: &EdgeConfig,
+    cluster_info: &KubernetesClusterInfo,
+) -> Result<ConfigMap, Error> {
+    let upstream_url = role_service_url(hello, cluster_info).context(BuildUpstreamUrlSnafu)?;
+    let cache_max_size = cache_max_size(rolegroup, merged_config)?;
+
+    ConfigMapBuilder::new()
+        .metadata(
+            ObjectMetaBuilder::new()
+                .name_and_namespace(hello)
+                .name(rolegroup.object_name())
+                .ownerreference_from_resource(hello, None, Some(true))
+                .context(ObjectMissingMetadataForOwnerRefSnafu)?
+                .with_recommended_labels(build_recommended_labels(
+                    hello,
+                    &resolved_product_image.app_version_label,
+                    &rolegroup.role,
+                    &rolegroup.role_group,
+                ))
+                .context(MetadataBuildSnafu)?
+                .build(),
+        )
+        .add_data(
+            NGINX_CONF,
+            nginx_conf(
+                &upstream_url,
+                hello.http_port(),
+                hello.server_secret_class().is_some(),
+                &merged_config.cache,
+                cache_max_size,
+            ),
+        )
+        .build()
+        .with_context(|_| BuildConfigMapSnafu {
+            rolegroup: rolegroup.clone(),
+        })
+}
+
+/// The rolegroup [`Deployment`] runs the reverse proxies of the role group.
+///
+/// The envOverrides and podOverrides of the role and the role group are applied, the
+/// configOverrides are ignored, as there is no product config for the reverse proxies.
+pub fn build_edge_rolegroup_deployment(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    rolegroup_ref: &RoleGroupRef<HelloCluster>,
+    merged_config: &EdgeConfig,
+    config_hash: &str,
+    sa_name: &str,
+) -> Result<Deployment, Error> {
+    let (edges, role_group) = hello
+        .spec
+        .edges
+        .as_ref()
+        .and_then(|edges| {
+            Some((
+                edges,
+                edges.role.role_groups.get(&rolegroup_ref.role_group)?,
+            ))
+        })
+        .with_context(|| RoleGroupNotDefinedSnafu {
+            rolegroup: rolegroup_ref.clone(),
+        })?;
+    let role = &edges.role;
+    let edge_image = edges
+        .image
+        .resolve(EDGE_IMAGE_BASE_NAME, crate::built_info::PKG_VERSION);
+
+    let mut container_builder =
+        ContainerBuilder::new(EDGE_CONTAINER_NAME).context(CreateContainerSnafu)?;
+
+    let mut env_overrides = BTreeMap::new();
+    env_overrides.extend(role.config.env_overrides.clone());
+    env_overrides.extend(role_group.config.env_overrides.clone());
+    for (name, value) in env_overrides {
+        container_builder.add_env_var(name, value);
+    }
+
+    let mut pod_builder = PodBuilder::new();
+
+    if let Some(server_secret_class) = hello.server_secret_class() {
+        // The certificate is valid for the edge role Service, the CA verifies the certificates of
+        // the servers
+        container_builder
+            .add_volume_mount(STACKABLE_TLS_DIR_NAME, STACKABLE_TLS_DIR)
+            .context(AddVolumeMountSnafu)?;
+        pod_builder
+            .add_volume(
+                VolumeBuilder::new(STACKABLE_TLS_DIR_NAME)
+                    .ephemeral(
+                        SecretOperatorVolumeSourceBuilder::new(server_secret_class)
+                            .with_pod_scope()
+                            .with_service_scope(
+                                hello
+                                    .edge_role_service_name()
+                                    .context(EdgeRoleServiceNameNotFoundSnafu)?,
+                            )
+                            .with_format(SecretFormat::TlsPem)
+                            .build()
+                            .context(TlsCaSecretClassVolumeBuildSnafu)?,
+                    )
+                    .build(),
+            )
+            .context(AddVolumeSnafu)?;
+    }
+
+    let container_nginx = container_builder
+        .image_from_product_image(&edge_image)
+        .command(vec![
+            "nginx".to_string(),
+            "-c".to_string(),
+            format!("{STACKABLE_CONFIG_DIR}/{NGINX_CONF}"),
+            "-g".to_string(),
+            "daemon off;".to_string(),
+        ])
+        .add_volume_mount(STACKABLE_CONFIG_DIR_NAME, STACKABLE_CONFIG_DIR)
+        .context(AddVolumeMountSnafu)?
+        .add_volume_mount(STACKABLE_CACHE_DIR_NAME, STACKABLE_CACHE_DIR)
+        .context(AddVolumeMountSnafu)?
+        .add_container_port(hello.http_port_name(), hello.http_port().into())
+        .resources(merged_config.resources.clone().into())
+        .readiness_probe(health_probe(hello))
+        .liveness_probe(health_probe(hello))
+        .build();
+
+    add_graceful_shutdown_config(merged_config.graceful_shutdown_timeout, &mut pod_builder)
+        .context(GracefulShutdownSnafu)?;
+
+    pod_builder
+        .metadata(
+            ObjectMetaBuilder::new()
+                .with_recommended_labels(build_recommended_labels(
+                    hello,
+                    &resolved_product_image.app_version_label,
+                    &rolegroup_ref.role,
+                    &rolegroup_ref.role_group,
+                ))
+                .context(MetadataBuildSnafu)?
+                .with_annotation(
+                    Annotation::try_from((CONFIG_HASH_ANNOTATION, config_hash))
+                        .context(AnnotationBuildSnafu)?,
+                )
+                .build(),
+        )
+        .image_pull_secrets_from_product_image(&edge_image)
+        .add_container(container_nginx)
+        .add_volume(Volume {
+            name: STACKABLE_CONFIG_DIR_NAME.to_string(),
+            config_map: Some(ConfigMapVolumeSource {
+                name: rolegroup_ref.object_name(),
+                ..ConfigMapVolumeSource::default()
+            }),
+            ..Volume::default()
+        })
+        .context(AddVolumeSnafu)?
+        .add_volume(Volume {
+            name: STACKABLE_CACHE_DIR_NAME.to_string(),
+            empty_dir: Some(EmptyDirVolumeSource {
+                medium: None,
+                size_limit: Some(merged_config.cache.max_size.clone()),
+            }),
+            ..Volume::default()
+        })
+        .context(AddVolumeSnafu)?
+        .affinity(&merged_config.affinity)
+        .service_account_name(sa_name);
+
+    let mut pod_template = pod_builder.build_template();
+    pod_template.merge_from(role.config.pod_overrides.clone());
+    pod_template.merge_from(role_group.config.pod_overrides.clone());
+
+    Ok(Deployment {
+        metadata: ObjectMetaBuilder::new()
+            .name_and_namespace(hello)
+            .name(rolegroup_ref.object_name())
+            .ownerreference_from_resource(hello, None, Some(true))
+            .context(ObjectMissingMetadataForOwnerRefSnafu)?
+            .with_recommended_labels(build_recommended_labels(
+                hello,
+                &resolved_product_image.app_version_label,
+                &rolegroup_ref.role,
+                &rolegroup_ref.role_group,
+            ))
+            .context(MetadataBuildSnafu)?
+            .build(),
+        spec: Some(DeploymentSpec {
+            replicas: role_group.replicas.map(i32::from),
+            selector: LabelSelector {
+                match_labels: Some(
+                    Labels::role_group_selector(
+                        hello,
+                        APP_NAME,
+                        &rolegroup_ref.role,
+                        &rolegroup_ref.role_group,
+                    )
+                    .context(LabelBuildSnafu)?
+                    .into(),
+                ),
+                ..LabelSelector::default()
+            },
+            template: pod_template,
+            ..DeploymentSpec::default()
+        }),
+        status: None,
+    })
+}
+
+/// The `nginx.conf` of a reverse proxy listening on the given port and forwarding all requests to
+/// the given upstream URL.
+///
+/// With `tls`, the reverse proxy serves HTTPS and verifies the certificates of the servers.
+/// Requests with credentials bypass the cache, so that the greeting of one user is never served
+/// to another one.
+///
+/// Spring Security marks every response as not cacheable, so the `Cache-Control` and `Expires`
+/// headers of the servers are ignored, otherwise nothing would ever be cached.
+fn nginx_conf(
+    upstream_url: &str,
+    port: u16,
+    tls: bool,
+    cache: &EdgeCacheConfig,
+    cache_max_size: MemoryQuantity,
+) -> String {
+    let cache_valid_seconds = cache.valid.as_secs();
+    let caching = cache_valid_seconds > 0;
+
+    let cache_path = if caching {
+        let cache_max_size_mebibytes =
+            (cache_max_size.scale_to(BinaryMultiple::Mebi).value * CACHE_VOLUME_USAGE).floor();
+        format!(
+            "    proxy_cache_path {STACKABLE_CACHE_DIR} levels=1:2 keys_zone=greeting:1m max_size={cache_max_size_mebibytes}m inactive={cache_valid_seconds}s use_temp_path=off;\n"
+        )
+    } else {
+        String::new()
+    };
+    let (listen_directives, upstream_tls_directives) = if tls {
+        (
+            format!(
+                r#"        listen {port} ssl;
+        ssl_certificate {STACKABLE_TLS_DIR}/tls.crt;
+        ssl_certificate_key {STACKABLE_TLS_DIR}/tls.key;
+"#
+            ),
+            format!(
+                r#"            proxy_ssl_verify on;
+            proxy_ssl_trusted_certificate {STACKABLE_TLS_DIR}/ca.crt;
+            proxy_ssl_server_name on;
+"#
+            ),
+        )
+    } else {
+        (format!("        listen {port};\n"), String::new())
+    };
+    let cache_directives = if caching {
+        format!(
+            r#"            proxy_cache greeting;
+            proxy_cache_valid 200 {cache_valid_seconds}s;
+            proxy_ignore_headers Cache-Control Expires;
+            proxy_cache_bypass $http_authorization $http_cookie;
+            proxy_no_cache $http_authorization $http_cookie;
+            add_header X-Cache-Status $upstream_cache_status;
+"#
+        )
+    } else {
+        String::new()
+    };
+
+    format!(
+        r#"pid /tmp/nginx.pid;
+worker_processes auto;
+error_log /dev/stderr notice;
+
+events {{
+    worker_connections 1024;
+}}
+
+http {{
+    access_log /dev/stdout;
+    client_body_temp_path /tmp/client_body;
+    proxy_temp_path /tmp/proxy;
+    fastcgi_temp_path /tmp/fastcgi;
+    uwsgi_temp_path /tmp/uwsgi;
+    scgi_temp_path /tmp/scgi;
+{cache_path}
+    server {{
+{listen_directives}
+        location = {HEALTH_PATH} {{
+            access_log off;
+            return 200 "ok\n";
+        }}
+
+        location / {{
+            proxy_pass {upstream_url};
+            proxy_set_header Host $host;
+            proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;
+            proxy_set_header X-Forwarded-Proto $scheme;
+{upstream_tls_directives}{cache_directives}        }}
+    }}
+}}
+"#
+    )
+}
+
+/// The reverse proxies answer the health endpoint themselves, so that they stay ready while the
+/// servers are restarted
+fn health_probe(hello: &HelloCluster) -> Probe {
+    let scheme = if hello.server_secret_class().is_some() {
+        "HTTPS"
+    } else {
+        "HTTP"
+    };
+
+    Probe {
+        http_get: Some(HTTPGetAction {
+            path: Some(HEALTH_PATH.to_owned()),
+            port: IntOrString::String(hello.http_port_name().to_owned()),
+            scheme: Some(scheme.to_owned()),
+            ..HTTPGetAction::default()
+        }),
+        period_seconds: Some(10),
+        failure_threshold: Some(3),
+        ..Probe::default()
+    }
+}
+
+fn service_ports(hello: &HelloCluster) -> Vec<ServicePort> {
+    vec![ServicePort {
+        name: Some(hello.http_port_name().to_string()),
+        port: hello.http_port().into(),
+        protocol: Some("TCP".to_string()),
+        ..ServicePort::default()
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+    use stackable_operator::{
+        k8s_openapi::apimachinery::pkg::api::resource::Quantity, time::Duration,
+    };
+
+    fn cache(valid: Duration) -> EdgeCacheConfig {
+        EdgeCacheConfig {
+            valid,
+            max_size: Quantity("100Mi".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_nginx_conf_caches_requests_without_credentials() {
+        let nginx_conf = nginx_conf(
+            "http://hello-world.default.svc.cluster.local:8080",
+            8080,
+            false,
+            &cache(Duration::from_secs(30)),
+            MemoryQuantity::from_mebi(100.0),
+        );
+
+        assert!(
+            nginx_conf.contains("proxy_pass http://hello-world.default.svc.cluster.local:8080;")
+        );
+        assert!(nginx_conf.contains("max_size=90m inactive=30s"));
+        assert!(nginx_conf.contains("proxy_cache_valid 200 30s;"));
+        assert!(nginx_conf.contains("proxy_no_cache $http_authorization $http_cookie;"));
+        assert!(!nginx_conf.contains("proxy_ssl_verify"));
+    }
+
+    #[test]
+    fn test_nginx_conf_ignores_the_cache_headers_of_the_servers() {
+        let nginx_conf = nginx_conf(
+            "http://hello-world.default.svc.cluster.local:8080",
+            8080,
+            false,
+            &cache(Duration::from_secs(30)),
+            MemoryQuantity::from_mebi(100.0),
+        );
+
+        // Spring Security sends `Cache-Control: no-cache, no-store, max-age=0, must-revalidate`
+        // and `Expires: 0`, which would prevent caching
+        assert!(nginx_conf.contains("proxy_ignore_headers Cache-Control Expires;"));
+        // Requests with credentials must still bypass the cache
+        assert!(nginx_conf.contains("proxy_cache_bypass $http_authorization $http_cookie;"));
+        assert!(nginx_conf.contains("proxy_no_cache $http_authorization $http_cookie;"));
+    }
+
+    #[rstest]
+    #[case::default_port(8080)]
+    #[case::custom_port(9090)]
+    fn test_nginx_conf_listens_on_the_http_port(#[case] port: u16) {
+        let nginx_conf = nginx_conf(
+            "http://hello-world.default.svc.cluster.local:8080",
+            port,
+            false,
+            &cache(Duration::from_secs(30)),
+            MemoryQuantity::from_mebi(100.0),
+        );
+
+        assert!(nginx_conf.contains(&format!("listen {port};")));
+    }
+
+    #[rstest]
+    #[case::without_tls(false)]
+    #[case::with_tls(true)]
+    fn test_nginx_conf_without_cache(#[case] tls: bool) {
+        let nginx_conf = nginx_conf(
+            "https://hello-world.default.svc.cluster.local:8080",
+            8080,
+            tls,
+            &cache(Duration::from_secs(0)),
+            MemoryQuantity::from_mebi(100.0),
+        );
+
+        assert!(!nginx_conf.contains("proxy_cache"));
+        assert_eq!(nginx_conf.contains("listen 8080 ssl;"), tls);
+        assert_eq!(
+            nginx_conf.contains("ssl_certificate /stackable/tls/tls.crt;"),
+            tls
+        );
+        assert_eq!(nginx_conf.contains("proxy_ssl_verify on;"), tls);
+    }
+}
@@ -0,0 +1,285 @@
+//! Validation of the greeting, so that typos or injection attempts never reach the greeting page,
+//! which embeds the recipient and the color into HTML.
+use snafu::{ensure, Snafu};
+
+use crate::crd::GreetingConfig;
+
+/// Characters which could be used to inject markup into the greeting page
+const MARKUP_CHARACTERS: [char; 5] = ['<', '>', '&', '"', '\''];
+
+/// Functional CSS color notations, e.g. `rgb(0 0 255)`
+const CSS_COLOR_FUNCTIONS: [&str; 9] = [
+    "rgb", "rgba", "hsl", "hsla", "hwb", "lab", "lch", "oklab", "oklch",
+];
+
+/// Named CSS colors, see <https://www.w3.org/TR/css-color-4/#named-colors>
+const CSS_NAMED_COLORS: [&str; 150] = [
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "currentcolor",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "transparent",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+#[derive(Snafu, Debug, PartialEq)]
+pub enum Error {
+    #[snafu(display("the color {color:?} is not a valid CSS color"))]
+    InvalidColor { color: String },
+
+    #[snafu(display(
+        "the recipient {recipient:?} must not contain the markup character {character:?}"
+    ))]
+    RecipientContainsMarkup { recipient: String, character: char },
+
+    #[snafu(display("the recipient {recipient:?} must not contain control characters"))]
+    RecipientContainsControlCharacters { recipient: String },
+}
+
+/// Checks that the greeting can be safely embedded into the greeting page
+pub fn validate_greeting(greeting: &GreetingConfig) -> Result<(), Error> {
+    validate_recipient(&greeting.recipient)?;
+    validate_color(&greeting.color)
+}
+
+fn validate_recipient(recipient: &str) -> Result<(), Error> {
+    if let Some(character) = recipient.chars().find(|c| MARKUP_CHARACTERS.contains(c)) {
+        return RecipientContainsMarkupSnafu {
+            recipient,
+            character,
+        }
+        .fail();
+    }
+    ensure!(
+        !recipient.chars().any(char::is_control),
+        RecipientContainsControlCharactersSnafu { recipient }
+    );
+
+    Ok(())
+}
+
+fn validate_color(color: &str) -> Result<(), Error> {
+    ensure!(is_css_color(color), InvalidColorSnafu { color });
+
+    Ok(())
+}
+
+fn is_css_color(color: &str) -> bool {
+    let color = color.trim().to_ascii_lowercase();
+
+    if let Some(hex) = color.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+
+    if let Some((function, arguments)) = color.split_once('(') {
+        return CSS_COLOR_FUNCTIONS.contains(&function.trim_end())
+            && arguments.strip_suffix(')').is_some_and(|arguments| {
+                !arguments.trim().is_empty()
+                    && arguments.chars().all(|c| {
+                        c.is_ascii_alphanumeric()
+                            || matches!(c, '.' | '%' | ',' | '/' | ' ' | '+' | '-')
+                    })
+            });
+    }
+
+    CSS_NAMED_COLORS.contains(&color.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("blue")]
+    #[case("RebeccaPurple")]
+    #[case("#00f")]
+    #[case("#0000ffcc")]
+    #[case("rgb(0 0 255)")]
+    #[case("rgba(0, 0, 255, 0.5)")]
+    #[case("hsl(240deg 100% 50% / 50%)")]
+    fn test_valid_colors(#[case] color: &str) {
+        assert_eq!(validate_color(color), Ok(()));
+    }
+
+    #[rstest]
+    #[case("bleu")]
+    #[case("")]
+    #[case("#00g")]
+    #[case("#00000")]
+    #[case("rgb()")]
+    #[case("rgb(0 0 255")]
+    #[case("url(https://example.com)")]
+    #[case("blue\"><script>alert(1)</script>")]
+    #[case("red; background: url(x)")]
+    fn test_invalid_colors(#[case] color: &str) {
+        assert_eq!(
+            validate_color(color),
+            Err(Error::InvalidColor {
+                color: color.to_owned()
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("Stackable", None)]
+    #[case("Data Engineers (EMEA)", None)]
+    #[case("<b>Stackable</b>", Some('<'))]
+    #[case("Tom & Jerry", Some('&'))]
+    #[case("\" onmouseover=\"alert(1)", Some('"'))]
+    fn test_recipient_markup(#[case] recipient: &str, #[case] character: Option<char>) {
+        let expected = match character {
+            Some(character) => Err(Error::RecipientContainsMarkup {
+                recipient: recipient.to_owned(),
+                character,
+            }),
+            None => Ok(()),
+        };
+        assert_eq!(validate_recipient(recipient), expected);
+    }
+}
@@ -0,0 +1,472 @@
+//! Exposes the role Service of the servers through an [`Ingress`] or a Gateway API [`HttpRoute`].
+//!
+//! The `ClusterResources` of the stackable-operator only clean up a fixed set of kinds, which
+//! contains neither [`Ingress`]es nor [`HttpRoute`]s, so they are applied by this module and
+//! cleaned up by [`crate::orphans`] instead.
+use std::{collections::BTreeSet, fmt::Debug};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use stackable_operator::{
+    builder::meta::ObjectMetaBuilder,
+    client::Client,
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::{
+        api::networking::v1::{
+            HTTPIngressPath, HTTPIngressRuleValue, Ingress, IngressBackend, IngressRule,
+            IngressServiceBackend, IngressSpec, ServiceBackendPort,
+        },
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+        NamespaceResourceScope,
+    },
+    kube::{core::GroupVersionKind, discovery::pinned_kind, CustomResource, Resource, ResourceExt},
+    kvp::Labels,
+};
+
+use crate::{
+    controller::build_recommended_labels,
+    crd::{HelloCluster, HelloHttpRoute, HelloIngress, HelloRole},
+    orphans::delete_orphaned_resources,
+    OPERATOR_NAME,
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object is missing metadata to build owner reference"))]
+    ObjectMissingMetadataForOwnerRef {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build Metadata"))]
+    MetadataBuild {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to calculate the role service name"))]
+    RoleServiceNameNotFound,
+
+    #[snafu(display("the ingress host must not be empty"))]
+    EmptyHost,
+
+    #[snafu(display("the ingress path {path:?} is invalid: it must start with a \"/\""))]
+    InvalidPath { path: String },
+
+    #[snafu(display(
+        "the ingress cannot be combined with TLS, because it connects to the servers via HTTP"
+    ))]
+    IngressWithTls,
+
+    #[snafu(display("failed to apply {kind} {name}"))]
+    Apply {
+        source: stackable_operator::client::Error,
+        kind: String,
+        name: String,
+    },
+
+    #[snafu(display("failed to delete the orphaned Ingresses and HTTPRoutes"))]
+    DeleteOrphans { source: crate::orphans::Error },
+}
+
+/// The subset of the Gateway API `HTTPRoute` which is needed to route requests to the role
+/// Service of the servers
+#[derive(Clone, CustomResource, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[kube(
+    group = "gateway.networking.k8s.io",
+    version = "v1",
+    kind = "HTTPRoute",
+    struct = "HttpRoute",
+    plural = "httproutes",
+    namespaced,
+    schema = "disabled",
+    crates(
+        kube_core = "stackable_operator::kube::core",
+        k8s_openapi = "stackable_operator::k8s_openapi",
+        schemars = "stackable_operator::schemars"
+    )
+)]
+pub struct HttpRouteSpec {
+    #[serde(default)]
+    pub parent_refs: Vec<HttpRouteParentRef>,
+    #[serde(default)]
+    pub hostnames: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<HttpRouteRule>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRouteParentRef {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRouteRule {
+    #[serde(default)]
+    pub matches: Vec<HttpRouteMatch>,
+    #[serde(default)]
+    pub backend_refs: Vec<HttpRouteBackendRef>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRouteMatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<HttpRoutePathMatch>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRoutePathMatch {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRouteBackendRef {
+    pub name: String,
+    pub port: u16,
+}
+
+/// The host must be set, as an empty host would route all hosts to the servers. The path must be
+/// absolute, because both the [`Ingress`] and the [`HttpRoute`] match it as a prefix of the
+/// request path. The servers must not serve TLS, as neither the [`Ingress`] nor the [`HttpRoute`]
+/// can be told to connect to them via HTTPS.
+pub fn validate_ingress(hello: &HelloCluster) -> Result<(), Error> {
+    if let Some(ingress) = &hello.spec.cluster_config.ingress {
+        ensure!(!ingress.host.is_empty(), EmptyHostSnafu);
+        ensure!(hello.server_secret_class().is_none(), IngressWithTlsSnafu);
+        ensure!(
+            ingress.path.starts_with('/'),
+            InvalidPathSnafu {
+                path: &ingress.path
+            }
+        );
+    }
+    Ok(())
+}
+
+/// The [`Ingress`] routes all requests for the host and path to the role Service of the servers.
+pub fn build_ingress(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    ingress: &HelloIngress,
+) -> Result<Ingress, Error> {
+    let role_service_name = hello
+        .server_role_service_name()
+        .context(RoleServiceNameNotFoundSnafu)?;
+
+    Ok(Ingress {
+        metadata: build_metadata(hello, resolved_product_image)?,
+        spec: Some(IngressSpec {
+            ingress_class_name: ingress.ingress_class_name.clone(),
+            rules: Some(vec![IngressRule {
+                host: Some(ingress.host.clone()),
+                http: Some(HTTPIngressRuleValue {
+                    paths: vec![HTTPIngressPath {
+                        path: Some(ingress.path.clone()),
+                        path_type: "Prefix".to_owned(),
+                        backend: IngressBackend {
+                            service: Some(IngressServiceBackend {
+                                name: role_service_name.to_owned(),
+                                port: Some(ServiceBackendPort {
+                                    number: Some(hello.http_port().into()),
+                                    ..ServiceBackendPort::default()
+                                }),
+                            }),
+                            ..IngressBackend::default()
+                        },
+                    }],
+                }),
+            }]),
+            ..IngressSpec::default()
+        }),
+        status: None,
+    })
+}
+
+/// The [`HttpRoute`] attaches to the given Gateway and routes all requests for the host and path
+/// to the role Service of the servers.
+pub fn build_http_route(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    ingress: &HelloIngress,
+    http_route: &HelloHttpRoute,
+) -> Result<HttpRoute, Error> {
+    let role_service_name = hello
+        .server_role_service_name()
+        .context(RoleServiceNameNotFoundSnafu)?;
+
+    Ok(HttpRoute {
+        metadata: build_metadata(hello, resolved_product_image)?,
+        spec: HttpRouteSpec {
+            parent_refs: vec![HttpRouteParentRef {
+                name: http_route.gateway_name.clone(),
+                namespace: http_route.gateway_namespace.clone(),
+            }],
+            hostnames: vec![ingress.host.clone()],
+            rules: vec![HttpRouteRule {
+                matches: vec![HttpRouteMatch {
+                    path: Some(HttpRoutePathMatch {
+                        type_: "PathPrefix".to_owned(),
+                        value: ingress.path.clone(),
+                    }),
+                }],
+                backend_refs: vec![HttpRouteBackendRef {
+                    name: role_service_name.to_owned(),
+                    port: hello.http_port(),
+                }],
+            }],
+        },
+    })
+}
+
+/// Applies the [`Ingress`] or the [`HttpRoute`] configured in `clusterConfig.ingress` and deletes
+/// all other ones of the cluster, e.g. because the `ingress` was removed or switched to the other
+/// kind.
+///
+/// Nothing is changed while the reconciliation is paused.
+pub async fn apply_ingress(
+    client: &Client,
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    cluster_labels: &Labels,
+) -> Result<(), Error> {
+    if hello.spec.cluster_operation.reconciliation_paused {
+        return Ok(());
+    }
+
+    let mut ingress_names = BTreeSet::new();
+    let mut http_route_names = BTreeSet::new();
+    match &hello.spec.cluster_config.ingress {
+        Some(
+            ingress @ HelloIngress {
+                http_route: Some(http_route),
+                ..
+            },
+        ) => {
+            let http_route = build_http_route(hello, resolved_product_image, ingress, http_route)?;
+            apply(client, &http_route).await?;
+            http_route_names.insert(http_route.name_any());
+        }
+        Some(ingress) => {
+            let ingress = build_ingress(hello, resolved_product_image, ingress)?;
+            apply(client, &ingress).await?;
+            ingress_names.insert(ingress.name_any());
+        }
+        None => {}
+    }
+
+    delete_orphaned_resources::<Ingress>(client, hello, cluster_labels, &ingress_names)
+        .await
+        .context(DeleteOrphansSnafu)?;
+    delete_orphaned_resources::<HttpRoute>(client, hello, cluster_labels, &http_route_names)
+        .await
+        .context(DeleteOrphansSnafu)
+}
+
+/// Whether the Kubernetes cluster serves [`HttpRoute`]s, i.e. whether the Gateway API is
+/// installed, so that they can be watched
+pub async fn http_routes_supported(client: &Client) -> bool {
+    let gvk = GroupVersionKind::gvk(
+        &HttpRoute::group(&()),
+        &HttpRoute::version(&()),
+        &HttpRoute::kind(&()),
+    );
+    pinned_kind(&client.as_kube_client(), &gvk).await.is_ok()
+}
+
+fn build_metadata(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+) -> Result<ObjectMeta, Error> {
+    Ok(ObjectMetaBuilder::new()
+        .name_and_namespace(hello)
+        .name(hello.name_any())
+        .ownerreference_from_resource(hello, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            hello,
+            &resolved_product_image.app_version_label,
+            &HelloRole::Server.to_string(),
+            "global",
+        ))
+        .context(MetadataBuildSnafu)?
+        .build())
+}
+
+async fn apply<K>(client: &Client, object: &K) -> Result<(), Error>
+where
+    K: Clone
+        + Debug
+        + DeserializeOwned
+        + Resource<DynamicType = (), Scope = NamespaceResourceScope>
+        + Serialize,
+{
+    client
+        .apply_patch(OPERATOR_NAME, object, object)
+        .await
+        .with_context(|_| ApplySnafu {
+            kind: K::kind(&()),
+            name: object.name_any(),
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(ingress: &str) -> HelloCluster {
+        let input = format!(
+            r#"
+            apiVersion: hello-world.stackable.tech/v1alpha2
+            kind: HelloCluster
+            metadata:
+              name: hello-world
+              namespace: default
+              uid: 3f1b8d5e-2a57-4c0e-9d64-0a8f1e7b6c21
+            spec:
+              image:
+                productVersion: 0.1.0
+              clusterConfig:
+                greeting:
+                  recipient: Stackable
+                  color: blue
+                httpPort: 8443
+                ingress: {ingress}
+              servers:
+                roleGroups:
+                  default:
+                    replicas: 1
+            "#
+        );
+        serde_yaml::from_str(&input).expect("illegal test input")
+    }
+
+    fn resolved_product_image(hello: &HelloCluster) -> ResolvedProductImage {
+        hello
+            .spec
+            .image
+            .resolve("hello", crate::built_info::PKG_VERSION)
+    }
+
+    #[test]
+    fn test_ingress_routes_to_the_role_service() {
+        let hello = hello("{host: hello.example.com, ingressClassName: nginx}");
+        let ingress = hello.spec.cluster_config.ingress.as_ref().unwrap();
+
+        let ingress = build_ingress(&hello, &resolved_product_image(&hello), ingress).unwrap();
+
+        assert_eq!(ingress.name_any(), "hello-world");
+        let spec = ingress.spec.unwrap();
+        assert_eq!(spec.ingress_class_name.as_deref(), Some("nginx"));
+        let rule = &spec.rules.unwrap()[0];
+        assert_eq!(rule.host.as_deref(), Some("hello.example.com"));
+        let path = &rule.http.as_ref().unwrap().paths[0];
+        assert_eq!(path.path.as_deref(), Some("/"));
+        assert_eq!(
+            path.backend.service,
+            Some(IngressServiceBackend {
+                name: "hello-world".to_owned(),
+                port: Some(ServiceBackendPort {
+                    number: Some(8443),
+                    ..ServiceBackendPort::default()
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_http_route_attaches_to_the_gateway() {
+        let hello = hello(
+            "{host: hello.example.com, path: /greeting, httpRoute: {gatewayName: public, gatewayNamespace: gateways}}",
+        );
+        let ingress = hello.spec.cluster_config.ingress.as_ref().unwrap();
+
+        let http_route = build_http_route(
+            &hello,
+            &resolved_product_image(&hello),
+            ingress,
+            ingress.http_route.as_ref().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            http_route.spec,
+            HttpRouteSpec {
+                parent_refs: vec![HttpRouteParentRef {
+                    name: "public".to_owned(),
+                    namespace: Some("gateways".to_owned()),
+                }],
+                hostnames: vec!["hello.example.com".to_owned()],
+                rules: vec![HttpRouteRule {
+                    matches: vec![HttpRouteMatch {
+                        path: Some(HttpRoutePathMatch {
+                            type_: "PathPrefix".to_owned(),
+                            value: "/greeting".to_owned(),
+                        }),
+                    }],
+                    backend_refs: vec![HttpRouteBackendRef {
+                        name: "hello-world".to_owned(),
+                        port: 8443,
+                    }],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_relative_path_is_rejected() {
+        let hello = hello("{host: hello.example.com, path: greeting}");
+
+        assert!(matches!(
+            validate_ingress(&hello),
+            Err(Error::InvalidPath { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ingress_with_tls_is_rejected() {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+            tls:
+              serverSecretClass: tls
+            ingress:
+              host: hello.example.com
+          servers:
+            roleGroups:
+              default:
+                replicas: 1
+        "#;
+        let hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+
+        assert!(matches!(
+            validate_ingress(&hello),
+            Err(Error::IngressWithTls)
+        ));
+    }
+
+    #[test]
+    fn test_empty_host_is_rejected() {
+        let hello = hello("{host: ''}");
+
+        assert!(matches!(validate_ingress(&hello), Err(Error::EmptyHost)));
+    }
+}
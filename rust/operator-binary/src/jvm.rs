@@ -0,0 +1,232 @@
+//! Computes the arguments of the JVM running the hello-world server.
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use stackable_operator::{
+    memory::{BinaryMultiple, MemoryQuantity},
+    role_utils::{JvmArgumentOverrides, RoleGroupRef},
+};
+
+use crate::crd::{
+    AnyRole, HelloCluster, HelloConfig, HelloRole, JVM_SECURITY_PROPERTIES, STACKABLE_CONFIG_DIR,
+};
+
+/// The shares of the non-heap memory which are reserved for the metaspace, the code cache and the
+/// direct buffers. The rest is left for thread stacks, the garbage collector and other native
+/// memory.
+const METASPACE_SHARE: f32 = 0.4;
+const CODE_CACHE_SHARE: f32 = 0.2;
+const DIRECT_MEMORY_SHARE: f32 = 0.2;
+/// Below this budget, the non-heap limits would be too small for the JVM to start
+const MIN_NON_HEAP_SIZE_IN_MEBI: u32 = 16;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("no memory limit specified"))]
+    MissingMemoryLimit,
+
+    #[snafu(display("invalid memory limit"))]
+    InvalidMemoryLimit {
+        source: stackable_operator::memory::Error,
+    },
+
+    #[snafu(display(
+        "the JVM heap percentage must be between 1 and 100, but is {heap_percentage}"
+    ))]
+    InvalidHeapPercentage { heap_percentage: u8 },
+
+    #[snafu(display("the {role} role does not run a JVM"))]
+    NoJvmRole { role: String },
+
+    #[snafu(display("failed to merge the jvmArgumentOverrides"))]
+    MergeJvmArgumentOverrides {
+        source: stackable_operator::role_utils::Error,
+    },
+}
+
+/// The arguments of the JVM, after applying the `jvmArgumentOverrides` of the role and the role
+/// group to the arguments generated by the operator.
+pub fn construct_jvm_args(
+    hello: &HelloCluster,
+    hello_role: &HelloRole,
+    role_group_ref: &RoleGroupRef<HelloCluster>,
+    merged_config: &HelloConfig,
+) -> Result<Vec<String>, Error> {
+    let Some(AnyRole::Server(role)) = hello.role(hello_role) else {
+        return NoJvmRoleSnafu {
+            role: hello_role.to_string(),
+        }
+        .fail();
+    };
+
+    let operator_generated =
+        JvmArgumentOverrides::new_with_only_additions(operator_generated_jvm_args(merged_config)?);
+    let merged = role
+        .get_merged_jvm_argument_overrides(&role_group_ref.role_group, &operator_generated)
+        .context(MergeJvmArgumentOverridesSnafu)?;
+
+    Ok(merged.effective_jvm_config_after_merging().clone())
+}
+
+/// The JVM arguments generated by the operator.
+///
+/// The heap size is derived from the memory limit of the container, so that raising the memory
+/// limit also gives the JVM more heap. The remaining memory is the budget of the non-heap memory,
+/// which is split between the metaspace, the code cache and the direct buffers, so that the JVM
+/// stays within the memory limit of the container.
+fn operator_generated_jvm_args(merged_config: &HelloConfig) -> Result<Vec<String>, Error> {
+    let (heap_size_in_mebi, non_heap_size_in_mebi) = memory_in_mebi(merged_config)?;
+    let non_heap_share = |share: f32| (non_heap_size_in_mebi as f32 * share).floor() as u32;
+
+    let mut jvm_args = vec![
+        format!("-Djava.security.properties={STACKABLE_CONFIG_DIR}/{JVM_SECURITY_PROPERTIES}"),
+        format!("-Xmx{heap_size_in_mebi}m"),
+        format!("-Xms{heap_size_in_mebi}m"),
+    ];
+    // Without a sufficient non-heap budget, e.g. with a heap percentage of 100, the JVM defaults
+    // are kept
+    if non_heap_size_in_mebi >= MIN_NON_HEAP_SIZE_IN_MEBI {
+        jvm_args.extend([
+            format!("-XX:MaxMetaspaceSize={}m", non_heap_share(METASPACE_SHARE)),
+            format!(
+                "-XX:ReservedCodeCacheSize={}m",
+                non_heap_share(CODE_CACHE_SHARE)
+            ),
+            format!(
+                "-XX:MaxDirectMemorySize={}m",
+                non_heap_share(DIRECT_MEMORY_SHARE)
+            ),
+        ]);
+    }
+
+    Ok(jvm_args)
+}
+
+/// The sizes of the heap and of the remaining non-heap memory
+fn memory_in_mebi(merged_config: &HelloConfig) -> Result<(u32, u32), Error> {
+    let memory = &merged_config.resources.memory;
+    let heap_percentage = memory.runtime_limits.heap_percentage;
+    ensure!(
+        (1..=100).contains(&heap_percentage),
+        InvalidHeapPercentageSnafu { heap_percentage }
+    );
+
+    let memory_limit =
+        MemoryQuantity::try_from(memory.limit.as_ref().context(MissingMemoryLimitSnafu)?)
+            .context(InvalidMemoryLimitSnafu)?
+            .scale_to(BinaryMultiple::Mebi);
+
+    let memory_limit_in_mebi = memory_limit.value.floor() as u32;
+    let heap_size_in_mebi =
+        (memory_limit.value * f32::from(heap_percentage) / 100.0).floor() as u32;
+
+    Ok((
+        heap_size_in_mebi,
+        memory_limit_in_mebi.saturating_sub(heap_size_in_mebi),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    fn jvm_args_for(servers: &str) -> Vec<String> {
+        let input = format!(
+            r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+          servers: {servers}
+        "#
+        );
+        let hello: HelloCluster = serde_yaml::from_str(&input).expect("illegal test input");
+        let role = HelloRole::Server;
+        let role_group_ref = role.rolegroup_ref(&hello, "default");
+        let merged_config = hello.merged_config(&role, &role_group_ref).unwrap();
+
+        construct_jvm_args(&hello, &role, &role_group_ref, &merged_config).unwrap()
+    }
+
+    #[rstest]
+    #[case::defaults("{}", 102, [61, 30, 30])]
+    #[case::raised_memory_limit("{resources: {memory: {limit: 1Gi}}}", 409, [246, 123, 123])]
+    #[case::custom_percentage(
+        "{resources: {memory: {limit: 1Gi, runtimeLimits: {heapPercentage: 50}}}}",
+        512,
+        [204, 102, 102]
+    )]
+    fn test_memory_follows_memory_limit(
+        #[case] config: &str,
+        #[case] expected_heap: u32,
+        #[case] expected_non_heap: [u32; 3],
+    ) {
+        let [expected_metaspace, expected_code_cache, expected_direct_memory] = expected_non_heap;
+        let jvm_args = jvm_args_for(&format!(
+            "{{config: {config}, roleGroups: {{default: {{replicas: 1}}}}}}"
+        ));
+
+        assert_eq!(
+            jvm_args,
+            vec![
+                "-Djava.security.properties=/stackable/config/security.properties".to_owned(),
+                format!("-Xmx{expected_heap}m"),
+                format!("-Xms{expected_heap}m"),
+                format!("-XX:MaxMetaspaceSize={expected_metaspace}m"),
+                format!("-XX:ReservedCodeCacheSize={expected_code_cache}m"),
+                format!("-XX:MaxDirectMemorySize={expected_direct_memory}m"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_non_heap_limits_without_non_heap_memory() {
+        let jvm_args = jvm_args_for(
+            "{config: {resources: {memory: {runtimeLimits: {heapPercentage: 100}}}}, roleGroups: {default: {replicas: 1}}}",
+        );
+
+        assert!(!jvm_args.iter().any(|arg| arg.starts_with("-XX:")));
+    }
+
+    #[test]
+    fn test_jvm_argument_overrides() {
+        let jvm_args = jvm_args_for(
+            r#"
+            jvmArgumentOverrides:
+              add:
+                - -Dhello=role
+                - -XX:+UseG1GC
+            roleGroups:
+              default:
+                replicas: 1
+                jvmArgumentOverrides:
+                  remove:
+                    - -XX:+UseG1GC
+                  removeRegex:
+                    - -Xm[sx].*
+                  add:
+                    - -Xmx1g
+                    - -Dhello=group"#,
+        );
+
+        assert_eq!(
+            jvm_args,
+            vec![
+                "-Djava.security.properties=/stackable/config/security.properties",
+                "-XX:MaxMetaspaceSize=61m",
+                "-XX:ReservedCodeCacheSize=30m",
+                "-XX:MaxDirectMemorySize=30m",
+                "-Dhello=role",
+                "-Xmx1g",
+                "-Dhello=group",
+            ]
+        );
+    }
+}
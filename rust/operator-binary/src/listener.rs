@@ -0,0 +1,209 @@
+//! Exposes the roles of a [`HelloCluster`] through [`Listener`]s of the listener-operator.
+//!
+//! One [`Listener`] is created per role. The Pods of the role mount a listener volume referencing
+//! it, so that the listener-operator knows which Pods to route the traffic to.
+use snafu::{ResultExt, Snafu};
+use stackable_operator::{
+    builder::meta::ObjectMetaBuilder,
+    commons::{
+        listener::{Listener, ListenerPort, ListenerSpec},
+        product_image_selection::ResolvedProductImage,
+    },
+    kube::ResourceExt,
+};
+
+use crate::{
+    controller::build_recommended_labels,
+    crd::{HelloCluster, HelloListenerAddress, HelloRole},
+};
+
+pub const LISTENER_VOLUME_NAME: &str = "listener";
+pub const LISTENER_VOLUME_DIR: &str = "/stackable/listener";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object is missing metadata to build owner reference"))]
+    ObjectMissingMetadataForOwnerRef {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build Metadata"))]
+    MetadataBuild {
+        source: stackable_operator::builder::meta::Error,
+    },
+}
+
+/// The name of the [`Listener`] shared by all role groups of the given role
+pub fn group_listener_name(hello: &HelloCluster, role: &HelloRole) -> String {
+    format!("{cluster}-{role}", cluster = hello.name_any())
+}
+
+/// The role [`Listener`] is picked up by the listener-operator, which exposes the Pods mounting
+/// the corresponding listener volume according to the given ListenerClass.
+pub fn build_group_listener(
+    hello: &HelloCluster,
+    resolved_product_image: &ResolvedProductImage,
+    role: &HelloRole,
+    listener_class: &str,
+) -> Result<Listener, Error> {
+    let role_name = role.to_string();
+
+    Ok(Listener {
+        metadata: ObjectMetaBuilder::new()
+            .name_and_namespace(hello)
+            .name(group_listener_name(hello, role))
+            .ownerreference_from_resource(hello, None, Some(true))
+            .context(ObjectMissingMetadataForOwnerRefSnafu)?
+            .with_recommended_labels(build_recommended_labels(
+                hello,
+                &resolved_product_image.app_version_label,
+                &role_name,
+                "global",
+            ))
+            .context(MetadataBuildSnafu)?
+            .build(),
+        spec: ListenerSpec {
+            class_name: Some(listener_class.to_owned()),
+            ports: Some(listener_ports(hello)),
+            ..ListenerSpec::default()
+        },
+        status: None,
+    })
+}
+
+/// Collects the addresses the listener-operator resolved for the given role [`Listener`].
+///
+/// The list is empty until the listener-operator has processed the [`Listener`].
+pub fn listener_addresses(role: &HelloRole, listener: &Listener) -> Vec<HelloListenerAddress> {
+    listener
+        .status
+        .as_ref()
+        .and_then(|status| status.ingress_addresses.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|ingress| HelloListenerAddress {
+            role: role.to_string(),
+            address: ingress.address.clone(),
+            ports: ingress.ports.clone(),
+        })
+        .collect()
+}
+
+fn listener_ports(hello: &HelloCluster) -> Vec<ListenerPort> {
+    vec![ListenerPort {
+        name: hello.http_port_name().to_string(),
+        port: hello.http_port().into(),
+        protocol: Some("TCP".to_string()),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello() -> HelloCluster {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+          uid: 3f1b8d5e-2a57-4c0e-9d64-0a8f1e7b6c21
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+            listenerClass: external-unstable
+          servers:
+            roleGroups:
+              default:
+                replicas: 1
+        "#;
+        serde_yaml::from_str(input).expect("illegal test input")
+    }
+
+    #[test]
+    fn test_group_listener() {
+        let hello = hello();
+        let resolved_product_image = hello
+            .spec
+            .image
+            .resolve("hello", crate::built_info::PKG_VERSION);
+
+        let listener = build_group_listener(
+            &hello,
+            &resolved_product_image,
+            &HelloRole::Server,
+            &hello.spec.cluster_config.listener_class,
+        )
+        .unwrap();
+
+        assert_eq!(
+            group_listener_name(&hello, &HelloRole::Server),
+            "hello-world-server"
+        );
+        assert_eq!(
+            group_listener_name(&hello, &HelloRole::Edge),
+            "hello-world-edge"
+        );
+        assert_eq!(listener.name_any(), "hello-world-server");
+        assert_eq!(
+            listener.spec.class_name.as_deref(),
+            Some("external-unstable")
+        );
+        let ports = listener.spec.ports.unwrap();
+        assert_eq!(ports.len(), 1);
+        assert_eq!(ports[0].name, "http");
+        assert_eq!(ports[0].port, 8080);
+    }
+
+    #[test]
+    fn test_listener_addresses() {
+        let listener: Listener = serde_yaml::from_str(
+            r#"
+            apiVersion: listeners.stackable.tech/v1alpha1
+            kind: Listener
+            metadata:
+              name: hello-world-server
+            spec: {}
+            status:
+              ingressAddresses:
+                - address: hello.example.com
+                  addressType: Hostname
+                  ports:
+                    http: 31080
+            "#,
+        )
+        .expect("illegal test input");
+
+        assert_eq!(
+            listener_addresses(&HelloRole::Server, &listener),
+            vec![HelloListenerAddress {
+                role: "server".to_owned(),
+                address: "hello.example.com".to_owned(),
+                ports: [("http".to_owned(), 31080)].into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_no_listener_addresses_before_the_listener_is_processed() {
+        let hello = hello();
+        let resolved_product_image = hello
+            .spec
+            .image
+            .resolve("hello", crate::built_info::PKG_VERSION);
+        let listener = build_group_listener(
+            &hello,
+            &resolved_product_image,
+            &HelloRole::Server,
+            "cluster-internal",
+        )
+        .unwrap();
+
+        assert!(listener_addresses(&HelloRole::Server, &listener).is_empty());
+    }
+}
@@ -1,31 +1,51 @@
 mod affinity;
+mod authentication;
+mod autoscaling;
 mod controller;
 mod crd;
+mod degraded;
+mod discovery;
+mod edge;
+mod greeting;
+mod ingress;
+mod jvm;
+mod listener;
+mod metrics;
 mod operations;
+mod orphans;
 mod product_logging;
+mod webhook;
 
-use crate::controller::HELLO_FULL_CONTROLLER_NAME;
+use crate::{
+    controller::HELLO_FULL_CONTROLLER_NAME, ingress::HttpRoute, metrics::Metrics,
+    webhook::WebhookOptions,
+};
 
 use clap::{crate_description, crate_version, Parser};
 use crd::{HelloCluster, APP_NAME};
 use futures::stream::StreamExt;
 use stackable_operator::{
     cli::{Command, ProductOperatorRun},
+    commons::listener::Listener,
     k8s_openapi::api::{
-        apps::v1::StatefulSet,
+        apps::v1::{Deployment, StatefulSet},
+        autoscaling::v2::HorizontalPodAutoscaler,
         core::v1::{ConfigMap, Service},
+        networking::v1::Ingress,
     },
     kube::{
         core::DeserializeGuard,
         runtime::{
+            controller::Error as ControllerError,
             events::{Recorder, Reporter},
+            reflector::ObjectRef,
             watcher, Controller,
         },
+        ResourceExt,
     },
-    logging::controller::report_controller_reconciled,
-    CustomResourceExt,
+    logging::controller::{report_controller_reconciled, ReconcilerError},
 };
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
 mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
@@ -37,19 +57,41 @@ const OPERATOR_NAME: &str = "hello.stackable.tech";
 #[clap(about, author)]
 struct Opts {
     #[clap(subcommand)]
-    cmd: Command,
+    cmd: Command<HelloRun>,
+}
+
+#[derive(clap::Parser)]
+struct HelloRun {
+    #[clap(flatten)]
+    common: ProductOperatorRun,
+
+    /// The address to serve the operator metrics on, e.g. `0.0.0.0:9090`.
+    /// No metrics are served if this is not set.
+    #[arg(long, env)]
+    metrics_address: Option<SocketAddr>,
+
+    #[clap(flatten)]
+    webhook: WebhookOptions,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let opts = Opts::parse();
     match opts.cmd {
-        Command::Crd => HelloCluster::print_yaml_schema(built_info::PKG_VERSION)?,
-        Command::Run(ProductOperatorRun {
-            product_config,
-            watch_namespace,
-            tracing_target,
-            cluster_info_opts,
+        Command::Crd => stackable_operator::yaml::serialize_to_explicit_document(
+            &mut std::io::stdout(),
+            &crd::merged_crd()?,
+        )?,
+        Command::Run(HelloRun {
+            common:
+                ProductOperatorRun {
+                    product_config,
+                    watch_namespace,
+                    tracing_target,
+                    cluster_info_opts,
+                },
+            metrics_address,
+            webhook,
         }) => {
             stackable_operator::logging::initialize_logging(
                 "HELLO_OPERATOR_LOG",
@@ -65,10 +107,10 @@ async fn main() -> anyhow::Result<()> {
                 built_info::RUSTC_VERSION,
             );
 
-            let product_config = product_config.load(&[
+            let product_config = Arc::new(product_config.load(&[
                 "deploy/config-spec/properties.yaml",
                 "/etc/stackable/hello-world-operator/config-spec/properties.yaml",
-            ])?;
+            ])?);
 
             let client = stackable_operator::client::initialize_operator(
                 Some(OPERATOR_NAME.to_string()),
@@ -84,49 +126,151 @@ async fn main() -> anyhow::Result<()> {
                 },
             ));
 
-            Controller::new(
+            if let Some(metrics_address) = metrics_address {
+                if let Err(error) = crate::metrics::install(metrics_address) {
+                    tracing::error!(
+                        error = &error as &dyn std::error::Error,
+                        "operator metrics are not served"
+                    );
+                }
+            }
+            let metrics = Arc::new(Metrics::new());
+
+            let webhooks = {
+                let client = client.clone();
+                let product_config = product_config.clone();
+                async move {
+                    match webhook.webhook_address {
+                        Some(webhook_address) => {
+                            crate::webhook::run(webhook_address, &webhook, client, product_config)
+                                .await
+                        }
+                        // Without webhooks only the controller stops the operator
+                        None => std::future::pending().await,
+                    }
+                }
+            };
+
+            let hello_controller = Controller::new(
                 watch_namespace.get_api::<DeserializeGuard<HelloCluster>>(&client),
                 watcher::Config::default(),
-            )
-            .owns(
-                watch_namespace.get_api::<DeserializeGuard<Service>>(&client),
-                watcher::Config::default(),
-            )
-            .owns(
-                watch_namespace.get_api::<DeserializeGuard<StatefulSet>>(&client),
-                watcher::Config::default(),
-            )
-            .owns(
-                watch_namespace.get_api::<DeserializeGuard<ConfigMap>>(&client),
-                watcher::Config::default(),
-            )
-            .shutdown_on_signal()
-            .run(
-                controller::reconcile_hello,
-                controller::error_policy,
-                Arc::new(controller::Ctx {
-                    client: client.clone(),
-                    product_config,
-                }),
-            )
-            // We can let the reporting happen in the background
-            .for_each_concurrent(
-                16, // concurrency limit
-                |result| {
-                    // The event_recorder needs to be shared across all invocations, so that
-                    // events are correctly aggregated
-                    let event_recorder = event_recorder.clone();
-                    async move {
-                        report_controller_reconciled(
-                            &event_recorder,
-                            HELLO_FULL_CONTROLLER_NAME,
-                            &result,
-                        )
-                        .await;
-                    }
-                },
-            )
-            .await;
+            );
+            let hello_store = hello_controller.store();
+            let hello_controller = hello_controller
+                .owns(
+                    watch_namespace.get_api::<DeserializeGuard<Service>>(&client),
+                    watcher::Config::default(),
+                )
+                .owns(
+                    watch_namespace.get_api::<DeserializeGuard<StatefulSet>>(&client),
+                    watcher::Config::default(),
+                )
+                .owns(
+                    watch_namespace.get_api::<DeserializeGuard<Deployment>>(&client),
+                    watcher::Config::default(),
+                )
+                .watches(
+                    watch_namespace.get_api::<DeserializeGuard<ConfigMap>>(&client),
+                    watcher::Config::default(),
+                    move |config_map| {
+                        // The owned ConfigMaps, like `Controller::owns` does
+                        let namespace = config_map.namespace();
+                        let owners = config_map
+                            .owner_references()
+                            .iter()
+                            .filter_map(|owner| {
+                                ObjectRef::from_owner_ref(namespace.as_deref(), owner, ())
+                            })
+                            .collect::<Vec<_>>();
+                        // Custom log ConfigMaps are not owned by the HelloClusters, but changes to
+                        // them must be rolled out nevertheless
+                        let referencing = hello_store
+                            .state()
+                            .into_iter()
+                            .filter(|hello| controller::references_config_map(hello, &config_map))
+                            .map(|hello| ObjectRef::from_obj(&*hello));
+                        owners.into_iter().chain(referencing).collect::<Vec<_>>()
+                    },
+                )
+                .owns(
+                    watch_namespace.get_api::<DeserializeGuard<Listener>>(&client),
+                    watcher::Config::default(),
+                )
+                .owns(
+                    watch_namespace.get_api::<DeserializeGuard<Ingress>>(&client),
+                    watcher::Config::default(),
+                )
+                .owns(
+                    watch_namespace.get_api::<DeserializeGuard<HorizontalPodAutoscaler>>(&client),
+                    watcher::Config::default(),
+                );
+            // Watching HTTPRoutes fails as long as the Gateway API is not installed
+            let hello_controller = if crate::ingress::http_routes_supported(&client).await {
+                hello_controller.owns(
+                    watch_namespace.get_api::<DeserializeGuard<HttpRoute>>(&client),
+                    watcher::Config::default(),
+                )
+            } else {
+                tracing::info!(
+                    "the Gateway API is not installed, changes of HTTPRoutes are not reconciled"
+                );
+                hello_controller
+            };
+            let controller = hello_controller
+                .shutdown_on_signal()
+                .run(
+                    |hello, ctx| {
+                        let metrics = metrics.clone();
+                        async move {
+                            let hello_ref = ObjectRef::from_obj(&*hello).erase();
+                            let _measurement = metrics.reconcile_started(&hello_ref);
+                            let result = controller::reconcile_hello(hello, ctx).await;
+                            if let Ok(action) = &result {
+                                metrics.reconcile_succeeded(hello_ref, action);
+                            }
+                            result
+                        }
+                    },
+                    |hello, error, ctx| {
+                        let hello_ref = ObjectRef::from_obj(&*hello).erase();
+                        let action = controller::error_policy(hello, error, ctx);
+                        metrics.reconcile_failed(hello_ref, error.category(), &action);
+                        action
+                    },
+                    Arc::new(controller::Ctx {
+                        client: client.clone(),
+                        product_config,
+                    }),
+                )
+                // We can let the reporting happen in the background
+                .for_each_concurrent(
+                    16, // concurrency limit
+                    |result| {
+                        // HelloClusters which were deleted after they were scheduled are not
+                        // reconciled anymore
+                        if let Err(ControllerError::ObjectNotFound(hello_ref)) = &result {
+                            metrics.reconcile_dropped(hello_ref);
+                        }
+                        // The event_recorder needs to be shared across all invocations, so that
+                        // events are correctly aggregated
+                        let event_recorder = event_recorder.clone();
+                        async move {
+                            report_controller_reconciled(
+                                &event_recorder,
+                                HELLO_FULL_CONTROLLER_NAME,
+                                &result,
+                            )
+                            .await;
+                        }
+                    },
+                );
+
+            // The webhooks are restarted by themselves if they fail, so only the controller stops
+            // the operator
+            tokio::select! {
+                () = controller => {}
+                () = webhooks => {}
+            }
         }
     }
 
@@ -0,0 +1,245 @@
+//! Statistics about the reconciliations of the operator itself, which are served in the
+//! Prometheus format by the exporter of the `metrics-exporter-prometheus` crate.
+//!
+//! The work queue of the controller is internal to kube-runtime. The queue depth therefore counts
+//! the HelloClusters which are requeued after their reconciliation, either by the returned
+//! [`Action`] or by the error policy, and whose next reconciliation has not started yet.
+//! Reconciliations which are triggered by watch events are not counted. Like in the scheduler of
+//! kube-runtime, a HelloCluster which is requeued several times before it is reconciled is only
+//! counted once.
+use std::{collections::HashSet, net::SocketAddr, sync::Mutex, time::Instant};
+
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder};
+use snafu::{ResultExt, Snafu};
+use stackable_operator::kube::{
+    core::DynamicObject,
+    runtime::{controller::Action, reflector::ObjectRef},
+};
+
+const RECONCILIATIONS: &str = "hello_operator_reconciliations_total";
+const RECONCILIATION_ERRORS: &str = "hello_operator_reconciliation_errors_total";
+const RECONCILIATION_DURATION: &str = "hello_operator_reconciliation_duration_seconds";
+const QUEUE_DEPTH: &str = "hello_operator_queue_depth";
+
+/// The default buckets of the Prometheus client libraries
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to serve the operator metrics on {address}"))]
+    InstallExporter {
+        source: BuildError,
+        address: SocketAddr,
+    },
+}
+
+/// Registers the metrics and serves them via HTTP on the given address
+pub fn install(address: SocketAddr) -> Result<(), Error> {
+    PrometheusBuilder::new()
+        .with_http_listener(address)
+        .set_buckets_for_metric(
+            Matcher::Full(RECONCILIATION_DURATION.to_owned()),
+            &DURATION_BUCKETS,
+        )
+        .and_then(PrometheusBuilder::install)
+        .context(InstallExporterSnafu { address })?;
+    describe();
+    tracing::info!(%address, "serving operator metrics");
+
+    Ok(())
+}
+
+fn describe() {
+    describe_counter!(
+        RECONCILIATIONS,
+        "Number of reconciliations of HelloClusters"
+    );
+    describe_counter!(
+        RECONCILIATION_ERRORS,
+        "Number of failed reconciliations of HelloClusters by error category"
+    );
+    describe_histogram!(
+        RECONCILIATION_DURATION,
+        "Duration of the reconciliations of HelloClusters"
+    );
+    describe_gauge!(
+        QUEUE_DEPTH,
+        "Number of HelloClusters which are requeued, but not yet reconciled again"
+    );
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    /// The HelloClusters which are requeued, but whose reconciliation has not started yet
+    queue: Mutex<HashSet<ObjectRef<DynamicObject>>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the start of the reconciliation of the given HelloCluster, which is taken out of
+    /// the queue. The reconciliation is considered finished, when the returned
+    /// [`ReconcileMeasurement`] is dropped.
+    pub fn reconcile_started(&self, hello: &ObjectRef<DynamicObject>) -> ReconcileMeasurement {
+        counter!(RECONCILIATIONS).increment(1);
+        self.update_queue(|queue| queue.remove(hello));
+
+        ReconcileMeasurement {
+            start: Instant::now(),
+        }
+    }
+
+    /// Records a successful reconciliation, which requeues the HelloCluster according to the
+    /// returned action
+    pub fn reconcile_succeeded(&self, hello: ObjectRef<DynamicObject>, action: &Action) {
+        self.requeue(hello, action);
+    }
+
+    /// Records a failed reconciliation with the category of its error, which requeues the
+    /// HelloCluster according to the action of the error policy
+    pub fn reconcile_failed(
+        &self,
+        hello: ObjectRef<DynamicObject>,
+        category: &'static str,
+        action: &Action,
+    ) {
+        counter!(RECONCILIATION_ERRORS, "category" => category).increment(1);
+        self.requeue(hello, action);
+    }
+
+    /// Records that the given requeued HelloCluster is not reconciled, because it was deleted in
+    /// the meantime
+    pub fn reconcile_dropped(&self, hello: &ObjectRef<DynamicObject>) {
+        self.update_queue(|queue| queue.remove(hello));
+    }
+
+    fn requeue(&self, hello: ObjectRef<DynamicObject>, action: &Action) {
+        if action != &Action::await_change() {
+            self.update_queue(|queue| queue.insert(hello));
+        }
+    }
+
+    fn update_queue(&self, update: impl FnOnce(&mut HashSet<ObjectRef<DynamicObject>>) -> bool) {
+        let mut queue = self
+            .queue
+            .lock()
+            .expect("metrics lock must not be poisoned");
+        update(&mut queue);
+        gauge!(QUEUE_DEPTH).set(queue.len() as f64);
+    }
+}
+
+pub struct ReconcileMeasurement {
+    start: Instant,
+}
+
+impl Drop for ReconcileMeasurement {
+    fn drop(&mut self) {
+        histogram!(RECONCILIATION_DURATION).record(self.start.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use metrics_exporter_prometheus::PrometheusRecorder;
+
+    use crate::crd::HelloCluster;
+
+    fn hello_ref(name: &str) -> ObjectRef<DynamicObject> {
+        ObjectRef::<HelloCluster>::new(name)
+            .within("default")
+            .erase()
+    }
+
+    fn recorder() -> PrometheusRecorder {
+        PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full(RECONCILIATION_DURATION.to_owned()),
+                &DURATION_BUCKETS,
+            )
+            .unwrap()
+            .build_recorder()
+    }
+
+    #[test]
+    fn test_render() {
+        let recorder = recorder();
+        let metrics = Metrics::new();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            describe();
+            drop(metrics.reconcile_started(&hello_ref("simple-hello")));
+            let _running = metrics.reconcile_started(&hello_ref("simple-hello"));
+            let requeue = Action::requeue(Duration::from_secs(5));
+            metrics.reconcile_failed(hello_ref("simple-hello"), "kubernetes", &requeue);
+            metrics.reconcile_failed(hello_ref("simple-hello"), "kubernetes", &requeue);
+            metrics.reconcile_failed(
+                hello_ref("other-hello"),
+                "configuration",
+                &Action::await_change(),
+            );
+        });
+        let rendered = recorder.handle().render();
+
+        assert!(rendered.contains("# TYPE hello_operator_reconciliations_total counter\n"));
+        assert!(rendered.contains("\nhello_operator_reconciliations_total 2\n"));
+        assert!(rendered.contains(
+            "\nhello_operator_reconciliation_errors_total{category=\"configuration\"} 1\n"
+        ));
+        assert!(rendered
+            .contains("\nhello_operator_reconciliation_errors_total{category=\"kubernetes\"} 2\n"));
+        assert!(
+            rendered.contains("# TYPE hello_operator_reconciliation_duration_seconds histogram\n")
+        );
+        assert!(rendered
+            .contains("\nhello_operator_reconciliation_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(rendered.contains("\nhello_operator_reconciliation_duration_seconds_count 2\n"));
+        // The failed reconciliations of simple-hello are requeued once, the one of other-hello
+        // awaits a change
+        assert!(rendered.contains("# TYPE hello_operator_queue_depth gauge\n"));
+        assert!(rendered.contains("\nhello_operator_queue_depth 1\n"));
+    }
+
+    #[test]
+    fn test_queue_depth() {
+        let recorder = recorder();
+        let metrics = Metrics::new();
+        let queue_depth = || {
+            recorder
+                .handle()
+                .render()
+                .lines()
+                .find_map(|line| line.strip_prefix("hello_operator_queue_depth "))
+                .map(str::to_owned)
+        };
+        let requeue = Action::requeue(Duration::from_secs(5));
+
+        ::metrics::with_local_recorder(&recorder, || {
+            metrics.reconcile_succeeded(hello_ref("first"), &requeue);
+            metrics.reconcile_succeeded(hello_ref("second"), &requeue);
+            // Requeuing a HelloCluster again before it is reconciled does not grow the queue
+            metrics.reconcile_failed(hello_ref("first"), "kubernetes", &requeue);
+        });
+        assert_eq!(queue_depth().as_deref(), Some("2"));
+
+        ::metrics::with_local_recorder(&recorder, || {
+            let _running = metrics.reconcile_started(&hello_ref("first"));
+        });
+        assert_eq!(queue_depth().as_deref(), Some("1"));
+
+        ::metrics::with_local_recorder(&recorder, || {
+            metrics.reconcile_succeeded(hello_ref("first"), &Action::await_change());
+            metrics.reconcile_dropped(&hello_ref("second"));
+        });
+        assert_eq!(queue_depth().as_deref(), Some("0"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Rolls out changes of the spec to the canary role group first.
+//!
+//! While a new revision of the spec is rolled out to the canary role group, the other role
+//! groups are rendered from the last known good spec. The new revision is promoted to all role
+//! groups once the canary role group was rolled out completely and stayed ready for the soak time.
+//!
+//! A revision identifies the spec without the replicas, see [`spec_revision`], so scaling never
+//! starts a soak.
+//!
+//! [`spec_revision`]: crate::operations::rollout::spec_revision
+use std::time::Duration;
+
+use stackable_operator::k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+use crate::crd::{HelloCanaryStatus, HelloCluster};
+
+/// Whether the given role group is the canary role group of the [`HelloCluster`]
+pub fn is_canary(hello: &HelloCluster, role_group: &str) -> bool {
+    hello
+        .spec
+        .cluster_config
+        .canary
+        .as_ref()
+        .is_some_and(|canary| canary.role_group == role_group)
+}
+
+/// Whether the role groups other than the canary role group must not receive the current spec
+/// with the given revision yet, because the canary role group was not promoted
+pub fn holds_back(hello: &HelloCluster, revision: &str) -> bool {
+    let promoted = hello
+        .status
+        .as_ref()
+        .and_then(|status| status.canary.as_ref())
+        .is_some_and(|canary| canary.revision == revision && canary.promoted);
+
+    hello.spec.cluster_config.canary.is_some() && !promoted
+}
+
+/// The progress of the canary role group for the current revision of the spec, and after which
+/// time the [`HelloCluster`] must be reconciled again to promote it.
+///
+/// Once promoted, a revision stays promoted, even if the canary role group becomes unready later
+/// on.
+pub fn canary_status(
+    hello: &HelloCluster,
+    revision: &str,
+    canary_rolled_out: bool,
+    now: &Time,
+) -> (Option<HelloCanaryStatus>, Option<Duration>) {
+    let (Some(canary), Some(status)) = (
+        hello.spec.cluster_config.canary.as_ref(),
+        hello.status.as_ref(),
+    ) else {
+        return (None, None);
+    };
+    // Without a last known good spec, there is nothing to fall back to, so the first spec is
+    // rolled out to all role groups at once.
+    if status
+        .last_known_good
+        .as_ref()
+        .is_none_or(|last_known_good| last_known_good.revision == revision)
+    {
+        return (None, None);
+    }
+
+    let previous = status
+        .canary
+        .as_ref()
+        .filter(|canary| canary.revision == revision);
+    if let Some(previous) = previous.filter(|previous| previous.promoted) {
+        return (Some(previous.clone()), None);
+    }
+    if !canary_rolled_out {
+        return (
+            Some(HelloCanaryStatus {
+                revision: revision.to_owned(),
+                ready_since: None,
+                promoted: false,
+            }),
+            None,
+        );
+    }
+
+    let ready_since = previous
+        .and_then(|previous| previous.ready_since.clone())
+        .unwrap_or_else(|| now.clone());
+    let ready_for = (now.0 - ready_since.0).to_std().unwrap_or_default();
+    let soak_time = *canary.soak_time;
+    let promoted = ready_for >= soak_time;
+
+    (
+        Some(HelloCanaryStatus {
+            revision: revision.to_owned(),
+            ready_since: Some(ready_since),
+            promoted,
+        }),
+        // The other role groups are rendered from the promoted spec in the next reconciliation
+        Some(if promoted {
+            Duration::from_secs(1)
+        } else {
+            soak_time - ready_for
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+    use stackable_operator::k8s_openapi::chrono::{DateTime, TimeDelta, Utc};
+
+    use crate::{
+        crd::{HelloClusterStatus, HelloLastKnownGood},
+        operations::rollout::pending_last_known_good,
+    };
+
+    /// The revision of the current spec, which differs from the one of the last known good spec
+    const CURRENT_REVISION: &str = "2";
+
+    /// A [`HelloCluster`] of generation 2, whose last known good spec of generation 1 greeted in
+    /// blue with 3 `default` replicas
+    fn hello(canary_status: Option<HelloCanaryStatus>) -> HelloCluster {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+          uid: 3f1b8d5e-2a57-4c0e-9d64-0a8f1e7b6c21
+          generation: 2
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: red
+            canary:
+              roleGroup: canary
+              soakTime: 10m
+          servers:
+            roleGroups:
+              canary:
+                replicas: 1
+              default:
+                replicas: 5
+        "#;
+        let mut hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+
+        let mut previous_spec = hello.spec.clone();
+        previous_spec.cluster_config.greeting.color = "blue".to_owned();
+        previous_spec
+            .servers
+            .role_groups
+            .get_mut("default")
+            .unwrap()
+            .replicas = Some(3);
+        hello.status = Some(HelloClusterStatus {
+            last_known_good: Some(HelloLastKnownGood {
+                generation: 1,
+                revision: "1".to_owned(),
+                spec: serde_json::to_string(&previous_spec).unwrap(),
+            }),
+            canary: canary_status,
+            ..HelloClusterStatus::default()
+        });
+
+        hello
+    }
+
+    /// The given number of minutes after the epoch
+    fn time(minutes: i64) -> Time {
+        Time(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::minutes(minutes))
+    }
+
+    fn canary(ready_since: Option<Time>, promoted: bool) -> HelloCanaryStatus {
+        HelloCanaryStatus {
+            revision: CURRENT_REVISION.to_owned(),
+            ready_since,
+            promoted,
+        }
+    }
+
+    #[test]
+    fn test_held_back_spec_keeps_the_current_replicas() {
+        let hello = hello(None);
+        assert!(holds_back(&hello, CURRENT_REVISION));
+
+        let previous =
+            pending_last_known_good(&hello, CURRENT_REVISION).expect("the spec must be held back");
+
+        assert_eq!(previous.spec.cluster_config.greeting.color, "blue");
+        assert_eq!(
+            previous.spec.servers.role_groups["default"].replicas,
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn test_promoted_spec_is_not_held_back() {
+        let promoted = canary(Some(time(0)), true);
+
+        assert!(holds_back(&hello(None), CURRENT_REVISION));
+        assert!(!holds_back(&hello(Some(promoted)), CURRENT_REVISION));
+    }
+
+    #[rstest]
+    #[case::canary_not_ready(None, false, None, None)]
+    #[case::canary_becomes_ready(None, true, Some(time(5)), Some(Duration::from_secs(600)))]
+    #[case::soaking(Some(time(0)), true, Some(time(0)), Some(Duration::from_secs(300)))]
+    #[case::canary_becomes_unready(Some(time(0)), false, None, None)]
+    fn test_canary_status(
+        #[case] ready_since: Option<Time>,
+        #[case] canary_rolled_out: bool,
+        #[case] expected_ready_since: Option<Time>,
+        #[case] expected_requeue: Option<Duration>,
+    ) {
+        let hello = hello(Some(canary(ready_since, false)));
+
+        let (status, requeue) =
+            canary_status(&hello, CURRENT_REVISION, canary_rolled_out, &time(5));
+
+        assert_eq!(status, Some(canary(expected_ready_since, false)));
+        assert_eq!(requeue, expected_requeue);
+    }
+
+    #[test]
+    fn test_canary_is_promoted_after_the_soak_time() {
+        let hello = hello(Some(canary(Some(time(0)), false)));
+
+        let (status, _) = canary_status(&hello, CURRENT_REVISION, true, &time(10));
+
+        assert!(status.unwrap().promoted);
+    }
+}
@@ -1,7 +1,5 @@
 use snafu::{ResultExt, Snafu};
-use stackable_operator::builder::pod::PodBuilder;
-
-use crate::crd::HelloConfig;
+use stackable_operator::{builder::pod::PodBuilder, time::Duration};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -11,13 +9,14 @@ pub enum Error {
     },
 }
 
+/// Sets the `gracefulShutdownTimeout` of the merged config of any role
 pub fn add_graceful_shutdown_config(
-    merged_config: &HelloConfig,
+    graceful_shutdown_timeout: Option<Duration>,
     pod_builder: &mut PodBuilder,
 ) -> Result<(), Error> {
     // This must be always set by the merge mechanism, as we provide a default value,
     // users can not disable graceful shutdown.
-    if let Some(graceful_shutdown_timeout) = merged_config.graceful_shutdown_timeout {
+    if let Some(graceful_shutdown_timeout) = graceful_shutdown_timeout {
         pod_builder
             .termination_grace_period(&graceful_shutdown_timeout)
             .context(SetTerminationGracePeriodSnafu)?;
@@ -1,2 +1,4 @@
+pub mod canary;
 pub mod graceful_shutdown;
 pub mod pdb;
+pub mod rollout;
@@ -36,6 +36,7 @@ pub async fn add_pdbs(
     }
     let max_unavailable = pdb.max_unavailable.unwrap_or(match role {
         HelloRole::Server => max_unavailable_servers(),
+        HelloRole::Edge => max_unavailable_edges(),
     });
     let pdb = PodDisruptionBudgetBuilder::new_with_role(
         hello,
@@ -61,3 +62,8 @@ pub async fn add_pdbs(
 fn max_unavailable_servers() -> u16 {
     1
 }
+
+/// The reverse proxies are interchangeable, so one of them can always be spared
+fn max_unavailable_edges() -> u16 {
+    1
+}
@@ -0,0 +1,626 @@
+//! Controls how changes to the Pods of a StatefulSet role group are rolled out.
+//!
+//! In the staged mode, a new revision of the Pod template is first rolled out to the Pod with the
+//! highest ordinal only. The operator lowers the partition of the rolling update by one, whenever
+//! all Pods of the role group are ready again, until all Pods run the new revision.
+//!
+//! Once a spec is rolled out to all role groups, it is kept as last known good snapshot in the
+//! status, so that role groups can be rendered from it while a newer spec is not trusted yet.
+//! Specs are compared by their [`spec_revision`], which leaves out the replicas and the cluster
+//! operation, as changes of them are applied to all role groups at once.
+//! If a progress deadline is set and no more Pods of the changed role groups become updated or
+//! ready within it, all role groups are rolled back to the last known good spec.
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+
+use fnv::FnvHasher;
+use snafu::{ResultExt, Snafu};
+use stackable_operator::{
+    commons::cluster_operation::ClusterOperation,
+    k8s_openapi::{
+        api::apps::v1::{RollingUpdateStatefulSetStrategy, StatefulSet, StatefulSetUpdateStrategy},
+        apimachinery::pkg::apis::meta::v1::Time,
+        chrono::Utc,
+    },
+};
+use tracing::warn;
+
+use crate::crd::{
+    HelloCluster, HelloClusterSpec, HelloLastKnownGood, HelloRolloutStatus, RollingUpdateConfig,
+};
+
+/// The hash of the Pod template which was applied last, so that a new revision can be detected
+/// before the StatefulSet controller has picked it up
+pub const POD_TEMPLATE_HASH_ANNOTATION: &str = "hello-world.stackable.tech/pod-template-hash";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to serialize the Pod template"))]
+    SerializePodTemplate { source: serde_json::Error },
+
+    #[snafu(display("failed to serialize the spec"))]
+    SerializeSpec { source: serde_json::Error },
+
+    #[snafu(display("failed to parse the last known good spec of generation {generation}"))]
+    ParseLastKnownGoodSpec {
+        source: serde_json::Error,
+        generation: i64,
+    },
+}
+
+/// Sets the update strategy of the given [`StatefulSet`].
+///
+/// `current` is the [`StatefulSet`] as it currently exists in the cluster, if any. It is needed to
+/// determine the next partition in the staged mode.
+pub fn add_update_strategy(
+    statefulset: &mut StatefulSet,
+    current: Option<&StatefulSet>,
+    rolling_update: &RollingUpdateConfig,
+) -> Result<(), Error> {
+    let template_hash = pod_template_hash(statefulset)?;
+    let replicas = statefulset
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.replicas)
+        .or_else(|| current.and_then(|current| current.spec.as_ref()?.replicas))
+        .unwrap_or(1);
+
+    let partition = if rolling_update.staged {
+        Some(staged_partition(replicas, &template_hash, current))
+    } else {
+        rolling_update.partition.map(i32::from)
+    };
+
+    statefulset
+        .metadata
+        .annotations
+        .get_or_insert_with(BTreeMap::new)
+        .insert(POD_TEMPLATE_HASH_ANNOTATION.to_owned(), template_hash);
+    if let Some(spec) = statefulset.spec.as_mut() {
+        spec.update_strategy = Some(StatefulSetUpdateStrategy {
+            type_: Some("RollingUpdate".to_owned()),
+            rolling_update: Some(RollingUpdateStatefulSetStrategy {
+                partition,
+                ..RollingUpdateStatefulSetStrategy::default()
+            }),
+        });
+    }
+
+    Ok(())
+}
+
+/// The partition of the rolling update of the given [`StatefulSet`]
+pub fn partition(statefulset: &StatefulSet) -> Option<i32> {
+    statefulset
+        .spec
+        .as_ref()?
+        .update_strategy
+        .as_ref()?
+        .rolling_update
+        .as_ref()?
+        .partition
+}
+
+/// Identifies the rendered spec. The replicas and the cluster operation are left out, so that
+/// scaling, pausing or stopping a cluster does not count as a change which must be rolled out.
+pub fn spec_revision(spec: &HelloClusterSpec) -> Result<String, Error> {
+    let mut spec = spec.clone();
+    spec.cluster_operation = ClusterOperation::default();
+    for role_group in spec.servers.role_groups.values_mut() {
+        role_group.replicas = None;
+    }
+    for role_group in spec
+        .edges
+        .iter_mut()
+        .flat_map(|edges| edges.role.role_groups.values_mut())
+    {
+        role_group.replicas = None;
+    }
+
+    // The role groups are kept in a HashMap, but the keys of a JSON Value are sorted
+    let mut hasher = FnvHasher::default();
+    serde_json::to_value(&spec)
+        .context(SerializeSpecSnafu)?
+        .to_string()
+        .hash(&mut hasher);
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// Takes a snapshot of the current spec with the given revision, which must only be done once it
+/// was rolled out successfully
+pub fn snapshot(hello: &HelloCluster, revision: &str) -> Result<Option<HelloLastKnownGood>, Error> {
+    let Some(generation) = hello.metadata.generation else {
+        return Ok(None);
+    };
+
+    Ok(Some(HelloLastKnownGood {
+        generation,
+        revision: revision.to_owned(),
+        spec: serde_json::to_string(&hello.spec).context(SerializeSpecSnafu)?,
+    }))
+}
+
+/// The given [`HelloCluster`] with the spec of the snapshot instead of its current one
+pub fn restore(
+    hello: &HelloCluster,
+    last_known_good: &HelloLastKnownGood,
+) -> Result<HelloCluster, Error> {
+    let spec: HelloClusterSpec =
+        serde_json::from_str(&last_known_good.spec).context(ParseLastKnownGoodSpecSnafu {
+            generation: last_known_good.generation,
+        })?;
+
+    Ok(HelloCluster {
+        metadata: hello.metadata.clone(),
+        spec,
+        status: hello.status.clone(),
+    })
+}
+
+/// The [`HelloCluster`] with the last known good spec, if the current spec with the given
+/// revision differs from it.
+///
+/// The replicas and the cluster operation are taken from the current spec, so that scaling never
+/// waits for a rollout.
+pub fn pending_last_known_good(hello: &HelloCluster, revision: &str) -> Option<HelloCluster> {
+    let last_known_good = hello.status.as_ref()?.last_known_good.as_ref()?;
+    if last_known_good.revision == revision {
+        return None;
+    }
+
+    let mut previous = match restore(hello, last_known_good) {
+        Ok(previous) => previous,
+        Err(error) => {
+            warn!(
+                error = &error as &dyn std::error::Error,
+                "The last known good spec cannot be restored, the current spec is rolled out to all role groups"
+            );
+            return None;
+        }
+    };
+    previous.spec.cluster_operation = hello.spec.cluster_operation.clone();
+    for (name, role_group) in &mut previous.spec.servers.role_groups {
+        if let Some(current) = hello.spec.servers.role_groups.get(name) {
+            role_group.replicas = current.replicas;
+        }
+    }
+
+    Some(previous)
+}
+
+/// Whether the rollout of the current spec with the given revision missed its deadline
+pub fn has_failed(hello: &HelloCluster, revision: &str) -> bool {
+    hello.spec.cluster_config.progress_deadline.is_some()
+        && hello
+            .status
+            .as_ref()
+            .and_then(|status| status.rollout.as_ref())
+            .is_some_and(|rollout| rollout.revision == revision && rollout.failed)
+}
+
+/// The Pods of the role groups which are rendered from the current spec
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RolloutProgress {
+    pub updated_replicas: i32,
+    pub ready_replicas: i32,
+    /// Whether all of these role groups are completely rolled out and ready
+    pub rolled_out: bool,
+}
+
+/// The progress of the rollout of the current spec with the given revision, and after which time
+/// the [`HelloCluster`] must be reconciled again to check the deadline.
+///
+/// The deadline starts anew whenever more Pods become updated or ready, and whenever the role
+/// groups are completely rolled out, so that the soak time of a canary role group does not count
+/// towards it. Without a deadline, rollouts are not tracked at all.
+pub fn rollout_status(
+    hello: &HelloCluster,
+    revision: &str,
+    progress: RolloutProgress,
+    now: &Time,
+) -> (Option<HelloRolloutStatus>, Option<Duration>) {
+    let (Some(deadline), Some(generation), Some(status)) = (
+        hello.spec.cluster_config.progress_deadline,
+        hello.metadata.generation,
+        hello.status.as_ref(),
+    ) else {
+        return (None, None);
+    };
+    // Without a last known good spec, there is nothing to roll back to
+    if status
+        .last_known_good
+        .as_ref()
+        .is_none_or(|last_known_good| last_known_good.revision == revision)
+    {
+        return (None, None);
+    }
+
+    let previous = status
+        .rollout
+        .as_ref()
+        .filter(|rollout| rollout.revision == revision);
+    if let Some(previous) = previous.filter(|previous| previous.failed) {
+        return (Some(previous.clone()), None);
+    }
+
+    let (last_progress_time, updated_replicas, ready_replicas) = match previous {
+        Some(previous)
+            if !progress.rolled_out
+                && progress.updated_replicas <= previous.updated_replicas
+                && progress.ready_replicas <= previous.ready_replicas =>
+        {
+            (
+                previous.last_progress_time.clone(),
+                previous.updated_replicas,
+                previous.ready_replicas,
+            )
+        }
+        _ => (
+            now.clone(),
+            progress.updated_replicas,
+            progress.ready_replicas,
+        ),
+    };
+    let deadline = *deadline;
+    let stalled_for = (now.0 - last_progress_time.0).to_std().unwrap_or_default();
+    let failed = stalled_for >= deadline;
+
+    (
+        Some(HelloRolloutStatus {
+            generation,
+            revision: revision.to_owned(),
+            last_progress_time,
+            updated_replicas,
+            ready_replicas,
+            failed,
+        }),
+        // The role groups are rendered from the last known good spec in the next reconciliation
+        Some(if failed {
+            Duration::from_secs(1)
+        } else {
+            deadline - stalled_for
+        }),
+    )
+}
+
+/// The current time, as stored in the status
+pub fn now() -> Time {
+    Time(Utc::now())
+}
+
+/// Why the given rollout failed, if it missed its deadline and the role groups were rolled back.
+/// It is reported in the `Degraded` condition, see [`crate::degraded`].
+pub fn rollout_failure(
+    hello: &HelloCluster,
+    rollout: Option<&HelloRolloutStatus>,
+) -> Option<String> {
+    let last_known_good_generation = hello
+        .status
+        .as_ref()
+        .and_then(|status| status.last_known_good.as_ref())
+        .map(|last_known_good| last_known_good.generation);
+
+    rollout
+        .filter(|rollout| rollout.failed)
+        .zip(last_known_good_generation)
+        .zip(hello.spec.cluster_config.progress_deadline)
+        .map(|((rollout, last_known_good_generation), deadline)| {
+            format!(
+                "generation {generation} made no progress within {deadline}, the role groups were rolled back to generation {last_known_good_generation}",
+                generation = rollout.generation,
+            )
+        })
+}
+
+fn pod_template_hash(statefulset: &StatefulSet) -> Result<String, Error> {
+    let template = statefulset.spec.as_ref().map(|spec| &spec.template);
+    let mut hasher = FnvHasher::default();
+    serde_json::to_string(&template)
+        .context(SerializePodTemplateSnafu)?
+        .hash(&mut hasher);
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// The next partition of a staged rollout:
+///
+/// * A new [`StatefulSet`] is rolled out at once, as there are no Pods to protect yet.
+/// * A new revision of the Pod template is rolled out to the Pod with the highest ordinal first.
+/// * The partition is lowered by one once the StatefulSet controller has caught up and all Pods,
+///   including the updated ones, are ready.
+fn staged_partition(replicas: i32, template_hash: &str, current: Option<&StatefulSet>) -> i32 {
+    let Some(current) = current else {
+        return 0;
+    };
+
+    let current_template_hash = current
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(POD_TEMPLATE_HASH_ANNOTATION));
+    if current_template_hash.map(String::as_str) != Some(template_hash) {
+        return (replicas - 1).max(0);
+    }
+
+    let current_partition = partition(current).unwrap_or(0).min(replicas);
+    let stage_is_ready = current.status.as_ref().is_some_and(|status| {
+        status.observed_generation == current.metadata.generation
+            && status.updated_replicas.unwrap_or(0) >= replicas - current_partition
+            && status.ready_replicas.unwrap_or(0) >= replicas
+    });
+
+    if stage_is_ready {
+        (current_partition - 1).max(0)
+    } else {
+        current_partition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+    use stackable_operator::k8s_openapi::{
+        api::apps::v1::{StatefulSetSpec, StatefulSetStatus},
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+        chrono::{DateTime, TimeDelta},
+    };
+
+    use crate::crd::HelloClusterStatus;
+
+    fn current(
+        template_hash: &str,
+        partition: i32,
+        updated_replicas: i32,
+        ready_replicas: i32,
+    ) -> StatefulSet {
+        StatefulSet {
+            metadata: ObjectMeta {
+                generation: Some(2),
+                annotations: Some(BTreeMap::from([(
+                    POD_TEMPLATE_HASH_ANNOTATION.to_owned(),
+                    template_hash.to_owned(),
+                )])),
+                ..ObjectMeta::default()
+            },
+            spec: Some(StatefulSetSpec {
+                replicas: Some(3),
+                update_strategy: Some(StatefulSetUpdateStrategy {
+                    type_: Some("RollingUpdate".to_owned()),
+                    rolling_update: Some(RollingUpdateStatefulSetStrategy {
+                        partition: Some(partition),
+                        ..RollingUpdateStatefulSetStrategy::default()
+                    }),
+                }),
+                ..StatefulSetSpec::default()
+            }),
+            status: Some(StatefulSetStatus {
+                observed_generation: Some(2),
+                replicas: 3,
+                updated_replicas: Some(updated_replicas),
+                ready_replicas: Some(ready_replicas),
+                ..StatefulSetStatus::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_new_statefulset_is_rolled_out_at_once() {
+        assert_eq!(staged_partition(3, "new", None), 0);
+    }
+
+    #[rstest]
+    #[case::new_revision(current("old", 0, 3, 3), 2)]
+    #[case::updated_pod_not_ready(current("new", 2, 1, 2), 2)]
+    #[case::updated_pod_ready(current("new", 2, 1, 3), 1)]
+    #[case::last_stage_ready(current("new", 1, 2, 3), 0)]
+    #[case::rolled_out(current("new", 0, 3, 3), 0)]
+    fn test_staged_partition(#[case] current: StatefulSet, #[case] expected_partition: i32) {
+        assert_eq!(
+            staged_partition(3, "new", Some(&current)),
+            expected_partition
+        );
+    }
+
+    /// The revision of the current spec, which differs from the one of the last known good spec
+    const CURRENT_REVISION: &str = "2";
+
+    /// A [`HelloCluster`] of generation 2, whose last known good spec of generation 1 greeted in
+    /// blue with 3 `default` replicas
+    fn hello(rollout: Option<HelloRolloutStatus>) -> HelloCluster {
+        let input = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+          uid: 3f1b8d5e-2a57-4c0e-9d64-0a8f1e7b6c21
+          generation: 2
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: red
+            progressDeadline: 10m
+          servers:
+            roleGroups:
+              canary:
+                replicas: 1
+              default:
+                replicas: 5
+        "#;
+        let mut hello: HelloCluster = serde_yaml::from_str(input).expect("illegal test input");
+
+        let mut previous_spec = hello.spec.clone();
+        previous_spec.cluster_config.greeting.color = "blue".to_owned();
+        previous_spec
+            .servers
+            .role_groups
+            .get_mut("default")
+            .unwrap()
+            .replicas = Some(3);
+        hello.status = Some(HelloClusterStatus {
+            last_known_good: Some(HelloLastKnownGood {
+                generation: 1,
+                revision: "1".to_owned(),
+                spec: serde_json::to_string(&previous_spec).unwrap(),
+            }),
+            rollout,
+            ..HelloClusterStatus::default()
+        });
+
+        hello
+    }
+
+    /// The given number of minutes after the epoch
+    fn time(minutes: i64) -> Time {
+        Time(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::minutes(minutes))
+    }
+
+    fn rollout(
+        last_progress_time: Time,
+        updated_replicas: i32,
+        ready_replicas: i32,
+        failed: bool,
+    ) -> HelloRolloutStatus {
+        HelloRolloutStatus {
+            generation: 2,
+            revision: CURRENT_REVISION.to_owned(),
+            last_progress_time,
+            updated_replicas,
+            ready_replicas,
+            failed,
+        }
+    }
+
+    fn progress(updated_replicas: i32, ready_replicas: i32, rolled_out: bool) -> RolloutProgress {
+        RolloutProgress {
+            updated_replicas,
+            ready_replicas,
+            rolled_out,
+        }
+    }
+
+    #[test]
+    fn test_last_known_good_keeps_the_current_replicas() {
+        let mut hello = hello(None);
+        hello.spec.cluster_operation.reconciliation_paused = true;
+
+        let previous =
+            pending_last_known_good(&hello, CURRENT_REVISION).expect("a change must be pending");
+
+        assert_eq!(previous.spec.cluster_config.greeting.color, "blue");
+        assert_eq!(
+            previous.spec.servers.role_groups["default"].replicas,
+            Some(5)
+        );
+        assert!(previous.spec.cluster_operation.reconciliation_paused);
+    }
+
+    #[test]
+    fn test_spec_revision_ignores_the_replicas() {
+        let hello = hello(None);
+        let revision = spec_revision(&hello.spec).unwrap();
+
+        let mut scaled = hello.spec.clone();
+        scaled
+            .servers
+            .role_groups
+            .get_mut("default")
+            .unwrap()
+            .replicas = Some(7);
+        scaled.cluster_operation.reconciliation_paused = true;
+        assert_eq!(spec_revision(&scaled).unwrap(), revision);
+
+        let mut changed = hello.spec.clone();
+        changed.cluster_config.greeting.color = "green".to_owned();
+        assert_ne!(spec_revision(&changed).unwrap(), revision);
+    }
+
+    #[rstest]
+    #[case::started(
+        None,
+        progress(0, 0, false),
+        rollout(time(5), 0, 0, false),
+        Duration::from_secs(600)
+    )]
+    #[case::stalled(
+        Some(rollout(time(0), 1, 1, false)),
+        progress(1, 1, false),
+        rollout(time(0), 1, 1, false),
+        Duration::from_secs(300)
+    )]
+    #[case::pod_updated(
+        Some(rollout(time(0), 1, 1, false)),
+        progress(2, 1, false),
+        rollout(time(5), 2, 1, false),
+        Duration::from_secs(600)
+    )]
+    #[case::pod_ready(
+        Some(rollout(time(0), 1, 1, false)),
+        progress(1, 2, false),
+        rollout(time(5), 1, 2, false),
+        Duration::from_secs(600)
+    )]
+    #[case::pod_lost(
+        Some(rollout(time(0), 2, 2, false)),
+        progress(2, 1, false),
+        rollout(time(0), 2, 2, false),
+        Duration::from_secs(300)
+    )]
+    #[case::rolled_out(
+        Some(rollout(time(0), 6, 6, false)),
+        progress(6, 6, true),
+        rollout(time(5), 6, 6, false),
+        Duration::from_secs(600)
+    )]
+    #[case::missed_deadline(
+        Some(rollout(time(-5), 1, 1, false)),
+        progress(1, 1, false),
+        rollout(time(-5), 1, 1, true),
+        Duration::from_secs(1)
+    )]
+    fn test_rollout_status(
+        #[case] previous: Option<HelloRolloutStatus>,
+        #[case] progress: RolloutProgress,
+        #[case] expected_status: HelloRolloutStatus,
+        #[case] expected_requeue: Duration,
+    ) {
+        let hello = hello(previous);
+
+        let (status, requeue) = rollout_status(&hello, CURRENT_REVISION, progress, &time(5));
+
+        assert_eq!(status, Some(expected_status));
+        assert_eq!(requeue, Some(expected_requeue));
+    }
+
+    #[test]
+    fn test_failed_rollout_stays_failed() {
+        let failed = rollout(time(0), 1, 1, true);
+        let hello = hello(Some(failed.clone()));
+
+        assert!(has_failed(&hello, CURRENT_REVISION));
+        assert_eq!(
+            rollout_status(&hello, CURRENT_REVISION, progress(6, 6, true), &time(20)),
+            (Some(failed.clone()), None)
+        );
+        assert!(rollout_failure(&hello, Some(&failed)).is_some());
+    }
+
+    #[test]
+    fn test_rollout_without_deadline_is_not_tracked() {
+        let mut hello = hello(Some(rollout(time(0), 1, 1, true)));
+        hello.spec.cluster_config.progress_deadline = None;
+
+        assert!(!has_failed(&hello, CURRENT_REVISION));
+        assert_eq!(
+            rollout_status(&hello, CURRENT_REVISION, progress(1, 1, false), &time(20)),
+            (None, None)
+        );
+    }
+}
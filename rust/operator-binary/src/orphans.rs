@@ -0,0 +1,95 @@
+//! Deletes the resources of a [`HelloCluster`] which are not covered by the `ClusterResources` of
+//! the stackable-operator, e.g. HorizontalPodAutoscalers, Ingresses and HTTPRoutes.
+//!
+//! Like `ClusterResources`, the resources of a cluster are found by the labels which all of them
+//! carry, i.e. the app name, the instance and the managing operator. This also finds the
+//! resources of removed role groups and roles, and of disabled features.
+use std::{collections::BTreeSet, fmt::Debug};
+
+use serde::de::DeserializeOwned;
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_operator::{
+    client::Client,
+    k8s_openapi::{apimachinery::pkg::apis::meta::v1::LabelSelector, NamespaceResourceScope},
+    kube::{
+        api::{DeleteParams, ListParams},
+        core::ErrorResponse,
+        Api, Resource, ResourceExt,
+    },
+    kvp::{LabelSelectorExt, Labels, SelectorError},
+};
+
+use crate::crd::HelloCluster;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object has no namespace"))]
+    ObjectHasNoNamespace,
+
+    #[snafu(display("failed to build the label selector of the cluster"))]
+    BuildLabelSelector { source: SelectorError },
+
+    #[snafu(display("failed to list the {kind}s of the cluster"))]
+    List {
+        source: stackable_operator::kube::Error,
+        kind: String,
+    },
+
+    #[snafu(display("failed to delete the orphaned {kind} {name}"))]
+    Delete {
+        source: stackable_operator::kube::Error,
+        kind: String,
+        name: String,
+    },
+}
+
+/// Deletes the objects of kind `K` which carry the `cluster_labels`, but whose names are not
+/// `desired_names`. A kind which is not served by the API server, e.g. the HTTPRoute as long as
+/// the Gateway API is not installed, has no objects to delete.
+pub async fn delete_orphaned_resources<K>(
+    client: &Client,
+    hello: &HelloCluster,
+    cluster_labels: &Labels,
+    desired_names: &BTreeSet<String>,
+) -> Result<(), Error>
+where
+    K: Clone
+        + Debug
+        + DeserializeOwned
+        + Resource<DynamicType = (), Scope = NamespaceResourceScope>,
+{
+    let namespace = hello.namespace().context(ObjectHasNoNamespaceSnafu)?;
+    let selector = LabelSelector {
+        match_labels: Some(cluster_labels.clone().into()),
+        ..LabelSelector::default()
+    }
+    .to_query_string()
+    .context(BuildLabelSelectorSnafu)?;
+    let api = Api::<K>::namespaced(client.as_kube_client(), &namespace);
+
+    let existing = match api.list(&ListParams::default().labels(&selector)).await {
+        Ok(existing) => existing.items,
+        Err(stackable_operator::kube::Error::Api(ErrorResponse { code: 404, .. })) => Vec::new(),
+        Err(source) => return Err(source).context(ListSnafu { kind: K::kind(&()) }),
+    };
+
+    for object in existing {
+        let name = object.name_any();
+        if desired_names.contains(&name) {
+            continue;
+        }
+        match api.delete(&name, &DeleteParams::default()).await {
+            Ok(_) => tracing::info!(kind = %K::kind(&()), name, "deleted orphaned resource"),
+            // Deleted in the meantime
+            Err(stackable_operator::kube::Error::Api(ErrorResponse { code: 404, .. })) => {}
+            Err(source) => {
+                return Err(source).context(DeleteSnafu {
+                    kind: K::kind(&()),
+                    name,
+                })
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -11,7 +11,11 @@ use stackable_operator::{
     memory::BinaryMultiple,
     product_logging::{
         self,
-        spec::{ContainerLogConfig, ContainerLogConfigChoice, Logging},
+        spec::{
+            ConfigMapLogConfig, ConfigMapLogConfigFragment, ContainerLogConfig,
+            ContainerLogConfigChoice, ContainerLogConfigChoiceFragment, ContainerLogConfigFragment,
+            CustomContainerLogConfig, CustomContainerLogConfigFragment, Logging, LoggingFragment,
+        },
     },
     role_utils::RoleGroupRef,
 };
@@ -30,6 +34,11 @@ pub enum Error {
         entry: &'static str,
         cm_name: String,
     },
+    #[snafu(display("failed to retrieve the custom log ConfigMap [{cm_name}]"))]
+    RetrieveCustomLogConfigMap {
+        source: stackable_operator::client::Error,
+        cm_name: String,
+    },
     #[snafu(display("crd validation failure"))]
     CrdValidationFailure { source: crate::crd::Error },
     #[snafu(display("vectorAggregatorConfigMapName must be set"))]
@@ -76,6 +85,67 @@ pub async fn resolve_vector_aggregator_address(
     Ok(vector_aggregator_address)
 }
 
+/// Return the name of the custom log ConfigMap of the given container, if one is configured
+pub fn custom_log_config_map<'a>(
+    logging: &'a Logging<Container>,
+    container: &Container,
+) -> Option<&'a str> {
+    match logging.containers.get(container) {
+        Some(ContainerLogConfig {
+            choice:
+                Some(ContainerLogConfigChoice::Custom(CustomContainerLogConfig {
+                    custom: ConfigMapLogConfig { config_map },
+                })),
+        }) => Some(config_map),
+        _ => None,
+    }
+}
+
+/// Return the name of the custom log ConfigMap of the given container in a role or role group
+/// config, which is not merged yet
+pub fn custom_log_config_map_fragment<'a>(
+    logging: &'a LoggingFragment<Container>,
+    container: &Container,
+) -> Option<&'a str> {
+    match logging.containers.get(container) {
+        Some(ContainerLogConfigFragment {
+            choice:
+                Some(ContainerLogConfigChoiceFragment::Custom(CustomContainerLogConfigFragment {
+                    custom:
+                        ConfigMapLogConfigFragment {
+                            config_map: Some(config_map),
+                        },
+                })),
+        }) => Some(config_map),
+        _ => None,
+    }
+}
+
+/// Return the custom log ConfigMap of the given container, if one is configured and exists
+pub async fn resolve_custom_log_config_map(
+    hello: &HelloCluster,
+    client: &Client,
+    logging: &Logging<Container>,
+    container: &Container,
+) -> Result<Option<ConfigMap>> {
+    let Some(config_map_name) = custom_log_config_map(logging, container) else {
+        return Ok(None);
+    };
+
+    client
+        .get_opt::<ConfigMap>(
+            config_map_name,
+            hello
+                .namespace()
+                .as_deref()
+                .context(ObjectHasNoNamespaceSnafu)?,
+        )
+        .await
+        .context(RetrieveCustomLogConfigMapSnafu {
+            cm_name: config_map_name.to_string(),
+        })
+}
+
 /// Extend the role group ConfigMap with logging and Vector configurations
 pub fn extend_role_group_config_map(
     rolegroup: &RoleGroupRef<HelloCluster>,
@@ -0,0 +1,373 @@
+//! Converts `HelloCluster`s between the served versions on behalf of the Kubernetes API server.
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_operator::kube::{
+    core::{
+        conversion::{ConversionRequest, ConversionResponse, ConversionReview},
+        Status,
+    },
+    Resource,
+};
+
+use crate::crd::{v1alpha1, v1alpha2};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("the object defines no apiVersion"))]
+    MissingApiVersion,
+
+    #[snafu(display("converting a HelloCluster from {from} to {to} is not supported"))]
+    UnsupportedConversion { from: String, to: String },
+
+    #[snafu(display("failed to parse the {api_version} HelloCluster"))]
+    ParseObject {
+        source: serde_json::Error,
+        api_version: String,
+    },
+
+    #[snafu(display("failed to convert the HelloCluster from {from} to {to}"))]
+    ConvertObject {
+        source: v1alpha1::Error,
+        from: String,
+        to: String,
+    },
+
+    #[snafu(display("failed to serialize the converted {api_version} HelloCluster"))]
+    SerializeObject {
+        source: serde_json::Error,
+        api_version: String,
+    },
+}
+
+/// Answers a `ConversionReview` of the Kubernetes API server.
+///
+/// The conversion is all or nothing, a single object which cannot be converted fails the whole
+/// review.
+pub fn convert(review: ConversionReview) -> ConversionReview {
+    let mut request = match ConversionRequest::from_review(review) {
+        Ok(request) => request,
+        Err(error) => {
+            tracing::warn!(%error, "received an invalid ConversionReview");
+            return ConversionResponse::invalid(Status::failure(
+                &error.to_string(),
+                "InvalidRequest",
+            ))
+            .into_review();
+        }
+    };
+
+    let converted = std::mem::take(&mut request.objects)
+        .into_iter()
+        .map(|object| convert_object(object, &request.desired_api_version))
+        .collect::<Result<Vec<_>, _>>();
+    let response = ConversionResponse::for_request(request);
+
+    match converted {
+        Ok(objects) => response.success(objects),
+        Err(error) => {
+            tracing::warn!(
+                error = &error as &dyn std::error::Error,
+                "conversion failed"
+            );
+            response.failure(Status::failure(&error.to_string(), "ConversionFailed"))
+        }
+    }
+    .into_review()
+}
+
+fn convert_object(object: Value, desired_api_version: &str) -> Result<Value, Error> {
+    let api_version = object
+        .get("apiVersion")
+        .and_then(Value::as_str)
+        .context(MissingApiVersionSnafu)?
+        .to_owned();
+
+    if api_version == desired_api_version {
+        return Ok(object);
+    }
+
+    let v1alpha1_api_version = v1alpha1::HelloCluster::api_version(&());
+    let v1alpha2_api_version = v1alpha2::HelloCluster::api_version(&());
+
+    if api_version == v1alpha1_api_version && desired_api_version == v1alpha2_api_version {
+        let hello: v1alpha1::HelloCluster = parse(object, &api_version)?;
+        let hello = v1alpha2::HelloCluster::try_from(hello).context(ConvertObjectSnafu {
+            from: api_version,
+            to: desired_api_version,
+        })?;
+        serialize(hello, desired_api_version)
+    } else if api_version == v1alpha2_api_version && desired_api_version == v1alpha1_api_version {
+        let hello: v1alpha2::HelloCluster = parse(object, &api_version)?;
+        let hello = v1alpha1::HelloCluster::try_from(hello).context(ConvertObjectSnafu {
+            from: api_version,
+            to: desired_api_version,
+        })?;
+        serialize(hello, desired_api_version)
+    } else {
+        UnsupportedConversionSnafu {
+            from: api_version,
+            to: desired_api_version,
+        }
+        .fail()
+    }
+}
+
+fn parse<T: DeserializeOwned>(object: Value, api_version: &str) -> Result<T, Error> {
+    serde_json::from_value(object).context(ParseObjectSnafu { api_version })
+}
+
+fn serialize<T: Serialize>(object: T, api_version: &str) -> Result<Value, Error> {
+    serde_json::to_value(object).context(SerializeObjectSnafu { api_version })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+    use stackable_operator::time::Duration;
+
+    use crate::crd::HelloCanary;
+
+    const V1ALPHA1: &str = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha1
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+        spec:
+          image:
+            productVersion: 0.1.0
+          recipient: Stackable
+          color: blue
+          clusterConfig:
+            listenerClass: external-unstable
+            authentication:
+              - authenticationClass: ldap
+            tls:
+              serverSecretClass: tls
+          servers:
+            config:
+              greeting:
+                color: red
+            configOverrides:
+              application.properties:
+                prop.role: role-value
+            roleGroups:
+              default:
+                replicas: 2
+                jvmArgumentOverrides:
+                  add:
+                    - -Dhello=group
+        status:
+          conditions: []
+        "#;
+
+    const V1ALPHA2: &str = r#"
+        apiVersion: hello-world.stackable.tech/v1alpha2
+        kind: HelloCluster
+        metadata:
+          name: hello-world
+          namespace: default
+        spec:
+          image:
+            productVersion: 0.1.0
+          clusterConfig:
+            greeting:
+              recipient: Stackable
+              color: blue
+            listenerClass: external-unstable
+            authentication:
+              - authenticationClass: ldap
+            tls:
+              serverSecretClass: tls
+          servers:
+            config:
+              greeting:
+                color: red
+            configOverrides:
+              application.properties:
+                prop.role: role-value
+            roleGroups:
+              default:
+                replicas: 2
+                jvmArgumentOverrides:
+                  add:
+                    - -Dhello=group
+        status:
+          conditions: []
+        "#;
+
+    fn review(desired_api_version: &str, objects: Vec<Value>) -> ConversionReview {
+        serde_json::from_value(json!({
+            "apiVersion": "apiextensions.k8s.io/v1",
+            "kind": "ConversionReview",
+            "request": {
+                "uid": "3d9b2a8c-52f6-4bd1-9bd2-d9f5c3b1e0a7",
+                "desiredAPIVersion": desired_api_version,
+                "objects": objects,
+            },
+        }))
+        .expect("illegal test input")
+    }
+
+    fn yaml_to_json(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).expect("illegal test input")
+    }
+
+    #[test]
+    fn test_convert_v1alpha1_to_v1alpha2() {
+        let hello: v1alpha1::HelloCluster =
+            serde_yaml::from_str(V1ALPHA1).expect("illegal test input");
+        let expected: v1alpha2::HelloCluster =
+            serde_yaml::from_str(V1ALPHA2).expect("illegal test input");
+
+        assert_eq!(v1alpha2::HelloCluster::try_from(hello).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_round_trip_from_v1alpha1() {
+        let hello: v1alpha1::HelloCluster =
+            serde_yaml::from_str(V1ALPHA1).expect("illegal test input");
+
+        let v1alpha2_hello = v1alpha2::HelloCluster::try_from(hello.clone()).unwrap();
+        let round_tripped = v1alpha1::HelloCluster::try_from(v1alpha2_hello).unwrap();
+
+        assert_eq!(round_tripped, hello);
+    }
+
+    #[test]
+    fn test_round_trip_from_v1alpha2() {
+        let hello: v1alpha2::HelloCluster =
+            serde_yaml::from_str(V1ALPHA2).expect("illegal test input");
+
+        let v1alpha1_hello = v1alpha1::HelloCluster::try_from(hello.clone()).unwrap();
+        let round_tripped = v1alpha2::HelloCluster::try_from(v1alpha1_hello).unwrap();
+
+        assert_eq!(round_tripped, hello);
+    }
+
+    #[test]
+    fn test_round_trip_preserves_v1alpha2_fields() {
+        let mut hello: v1alpha2::HelloCluster =
+            serde_yaml::from_str(V1ALPHA2).expect("illegal test input");
+        hello.spec.cluster_config.http_port = 9090;
+        hello.spec.cluster_config.canary = Some(HelloCanary {
+            role_group: "canary".to_owned(),
+            soak_time: Duration::from_minutes_unchecked(5),
+        });
+        hello.spec.cluster_config.progress_deadline = Some(Duration::from_minutes_unchecked(15));
+        hello.spec.cluster_config.ingress = Some(
+            serde_yaml::from_str(
+                "{host: hello.example.com, path: /greeting, httpRoute: {gatewayName: public}}",
+            )
+            .expect("illegal test input"),
+        );
+        hello.spec.edges = Some(
+            serde_yaml::from_str(
+                r#"
+                image:
+                  custom: docker.io/library/nginx:1.27.3
+                  productVersion: 1.27.3
+                roleGroups:
+                  default:
+                    replicas: 2
+                "#,
+            )
+            .expect("illegal test input"),
+        );
+        hello.spec.servers = serde_yaml::from_str(
+            r#"
+            config:
+              workload: Deployment
+              probes:
+                liveness:
+                  initialDelaySeconds: 60
+            roleGroups:
+              default:
+                replicas: 2
+                config:
+                  podManagementPolicy: OrderedReady
+                  rollingUpdate:
+                    staged: true
+                  autoscaling:
+                    maxReplicas: 5
+              other:
+                replicas: 1
+                config:
+                  greeting:
+                    color: red
+            "#,
+        )
+        .expect("illegal test input");
+
+        let v1alpha1_hello = v1alpha1::HelloCluster::try_from(hello.clone()).unwrap();
+
+        assert!(v1alpha1_hello
+            .metadata
+            .annotations
+            .as_ref()
+            .is_some_and(
+                |annotations| annotations.contains_key(v1alpha1::V1ALPHA2_FIELDS_ANNOTATION)
+            ));
+        assert_eq!(
+            v1alpha2::HelloCluster::try_from(v1alpha1_hello).unwrap(),
+            hello
+        );
+    }
+
+    #[test]
+    fn test_round_trip_preserves_missing_servers() {
+        let mut hello: v1alpha1::HelloCluster =
+            serde_yaml::from_str(V1ALPHA1).expect("illegal test input");
+        hello.spec.servers = None;
+
+        let v1alpha2_hello = v1alpha2::HelloCluster::try_from(hello.clone()).unwrap();
+
+        assert!(v1alpha2_hello.spec.servers.role_groups.is_empty());
+        assert_eq!(
+            v1alpha1::HelloCluster::try_from(v1alpha2_hello).unwrap(),
+            hello
+        );
+    }
+
+    #[test]
+    fn test_convert_review() {
+        let review = review(
+            "hello-world.stackable.tech/v1alpha2",
+            vec![yaml_to_json(V1ALPHA1), yaml_to_json(V1ALPHA2)],
+        );
+
+        let response = serde_json::to_value(convert(review)).unwrap();
+
+        assert_eq!(
+            response["response"]["uid"],
+            "3d9b2a8c-52f6-4bd1-9bd2-d9f5c3b1e0a7"
+        );
+        assert_eq!(response["response"]["result"]["status"], "Success");
+        let converted_objects = response["response"]["convertedObjects"].as_array().unwrap();
+        assert_eq!(converted_objects.len(), 2);
+        for converted_object in converted_objects {
+            let hello: v1alpha2::HelloCluster =
+                serde_json::from_value(converted_object.clone()).unwrap();
+            assert_eq!(
+                converted_object["apiVersion"],
+                "hello-world.stackable.tech/v1alpha2"
+            );
+            assert_eq!(hello.spec.cluster_config.greeting.recipient, "Stackable");
+        }
+    }
+
+    #[test]
+    fn test_convert_review_with_unsupported_version() {
+        let review = review(
+            "hello-world.stackable.tech/v1beta1",
+            vec![yaml_to_json(V1ALPHA1)],
+        );
+
+        let response = serde_json::to_value(convert(review)).unwrap();
+
+        assert_eq!(response["response"]["result"]["status"], "Failure");
+    }
+}
@@ -0,0 +1,388 @@
+//! Serves the webhooks of the operator via HTTPS:
+//!
+//! * the conversion webhook, which converts `HelloCluster`s between the served versions,
+//! * the validating admission webhook, which rejects invalid `HelloCluster`s and
+//! * the validating admission webhook of the `scale` subresource, which rejects scaling role groups
+//!   which do not exist.
+//!
+//! The webhooks run in the operator process itself. The Kubernetes API server reaches them via a
+//! Service in front of the operator. The conversion webhook is registered in the CRD on startup
+//! together with the CA certificate of the webhook certificate. The validating webhook is
+//! registered by the `ValidatingWebhookConfiguration` of the Helm chart instead, so that it is
+//! removed together with the operator and does not block changes to `HelloCluster`s afterwards.
+use std::{
+    convert::Infallible,
+    fs::File,
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use http_body_util::BodyExt;
+use hyper::{
+    body::Incoming, header, server::conn::http1, service::service_fn, Method, Request, Response,
+    StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use product_config::ProductConfigManager;
+use rustls::ServerConfig;
+use serde::{de::DeserializeOwned, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_operator::{
+    client::Client,
+    k8s_openapi::{
+        apiextensions_apiserver::pkg::apis::apiextensions::v1::{
+            CustomResourceConversion, CustomResourceDefinition, ServiceReference,
+            WebhookClientConfig, WebhookConversion,
+        },
+        ByteString,
+    },
+    kube::{
+        api::{Patch, PatchParams},
+        core::{admission::AdmissionReview, conversion::ConversionReview, DynamicObject},
+        Api, CustomResourceExt,
+    },
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsAcceptor;
+
+use crate::crd::HelloCluster;
+
+pub mod conversion;
+pub mod validation;
+
+pub const CONVERSION_PATH: &str = "/convert";
+pub const VALIDATION_PATH: &str = "/validate";
+pub const SCALE_VALIDATION_PATH: &str = "/validate-scale";
+const TLS_CERTIFICATE: &str = "tls.crt";
+const TLS_PRIVATE_KEY: &str = "tls.key";
+const TLS_CA_CERTIFICATE: &str = "ca.crt";
+/// Certificates issued by the secret-operator are short-lived, so they are reloaded regularly
+const TLS_RELOAD_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Failures like a missing certificate or an unreachable API server are usually temporary
+const RESTART_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(clap::Args, Debug)]
+pub struct WebhookOptions {
+    /// The address to serve the webhooks on, e.g. `0.0.0.0:8443`.
+    /// No webhooks are served if this is not set.
+    #[arg(long, env)]
+    pub webhook_address: Option<SocketAddr>,
+
+    /// The directory containing the `tls.crt`, `tls.key` and `ca.crt` used to serve the webhooks,
+    /// e.g. as provided by the Helm chart.
+    #[arg(long, env, default_value = "/stackable/webhook-tls")]
+    pub webhook_tls_dir: PathBuf,
+
+    /// The name of the Service through which the Kubernetes API server reaches the webhooks.
+    /// It must forward the port of the `webhook-address`.
+    #[arg(long, env, default_value = "hello-world-operator-webhook")]
+    pub webhook_service_name: String,
+
+    /// The namespace of the webhook Service, which is usually the namespace of the operator.
+    #[arg(long, env)]
+    pub webhook_service_namespace: Option<String>,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("the namespace of the webhook Service is not set"))]
+    MissingServiceNamespace,
+
+    #[snafu(display("failed to read the CA certificate {path:?}"))]
+    ReadCaCertificate {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to read the webhook certificate {path:?}"))]
+    ReadCertificate {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to read the private key {path:?}"))]
+    ReadPrivateKey {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("the file {path:?} does not contain a private key"))]
+    MissingPrivateKey { path: PathBuf },
+
+    #[snafu(display("the webhook certificate or private key is invalid"))]
+    InvalidTlsConfig { source: rustls::Error },
+
+    #[snafu(display("failed to serialize the conversion settings of the CRD"))]
+    SerializeCrdPatch { source: serde_json::Error },
+
+    #[snafu(display("failed to register the conversion webhook in the CRD {crd_name}"))]
+    PatchCrd {
+        source: stackable_operator::kube::Error,
+        crd_name: String,
+    },
+
+    #[snafu(display("failed to bind the webhooks to {address}"))]
+    BindWebhooks {
+        source: std::io::Error,
+        address: SocketAddr,
+    },
+}
+
+/// Serves the webhooks until the operator is stopped. The webhooks are restarted after a delay
+/// if they fail, so that the controller keeps running in the meantime.
+pub async fn run(
+    address: SocketAddr,
+    options: &WebhookOptions,
+    client: Client,
+    product_config: Arc<ProductConfigManager>,
+) {
+    loop {
+        if let Err(error) = serve(address, options, client.clone(), product_config.clone()).await {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                delay = ?RESTART_DELAY,
+                "the webhooks failed and are restarted after a delay"
+            );
+        }
+        tokio::time::sleep(RESTART_DELAY).await;
+    }
+}
+
+/// Registers the conversion webhook and serves the webhooks
+async fn serve(
+    address: SocketAddr,
+    options: &WebhookOptions,
+    client: Client,
+    product_config: Arc<ProductConfigManager>,
+) -> Result<(), Error> {
+    // Several crypto providers may be compiled in, so one has to be chosen explicitly. An error
+    // only means that a provider was already installed.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let certificate = options.webhook_tls_dir.join(TLS_CERTIFICATE);
+    let private_key = options.webhook_tls_dir.join(TLS_PRIVATE_KEY);
+    let tls_config = Arc::new(RwLock::new(load_tls_config(&certificate, &private_key)?));
+
+    let ca_certificate_path = options.webhook_tls_dir.join(TLS_CA_CERTIFICATE);
+    let ca_bundle = ByteString(tokio::fs::read(&ca_certificate_path).await.context(
+        ReadCaCertificateSnafu {
+            path: ca_certificate_path,
+        },
+    )?);
+    let service_namespace = options
+        .webhook_service_namespace
+        .clone()
+        .context(MissingServiceNamespaceSnafu)?;
+    let service_port = i32::from(address.port());
+
+    register_conversion_webhook(
+        &client,
+        &ca_bundle,
+        &options.webhook_service_name,
+        &service_namespace,
+        service_port,
+    )
+    .await?;
+
+    let listener = TcpListener::bind(address)
+        .await
+        .context(BindWebhooksSnafu { address })?;
+    tracing::info!(%address, "serving webhooks");
+
+    // Nothing fails after this point, so a restart of the webhooks never leaves a reloader behind
+    tokio::spawn({
+        let tls_config = tls_config.clone();
+        async move {
+            let mut interval = tokio::time::interval(TLS_RELOAD_INTERVAL);
+            // The first tick completes immediately, but the certificate was just loaded
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match load_tls_config(&certificate, &private_key) {
+                    Ok(reloaded) => {
+                        *tls_config.write().expect("TLS lock must not be poisoned") = reloaded
+                    }
+                    Err(error) => tracing::warn!(
+                        error = &error as &dyn std::error::Error,
+                        "failed to reload the webhook certificate"
+                    ),
+                }
+            }
+        }
+    });
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(error) => {
+                // E.g. the file descriptors are exhausted, which is not permanent
+                tracing::warn!(%error, "failed to accept a webhook connection");
+                continue;
+            }
+        };
+
+        // Every connection uses the most recently loaded certificate
+        let acceptor = TlsAcceptor::from(
+            tls_config
+                .read()
+                .expect("TLS lock must not be poisoned")
+                .clone(),
+        );
+        let client = client.clone();
+        let product_config = product_config.clone();
+        tokio::spawn(async move {
+            if let Err(error) = serve_connection(stream, acceptor, client, product_config).await {
+                tracing::debug!(%error, "failed to serve a webhook connection");
+            }
+        });
+    }
+}
+
+/// Loads the certificate chain and the private key from PEM files
+fn load_tls_config(certificate: &Path, private_key: &Path) -> Result<Arc<ServerConfig>, Error> {
+    let certificate_chain = rustls_pemfile::certs(&mut BufReader::new(
+        File::open(certificate).context(ReadCertificateSnafu { path: certificate })?,
+    ))
+    .collect::<Result<Vec<_>, _>>()
+    .context(ReadCertificateSnafu { path: certificate })?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(
+        File::open(private_key).context(ReadPrivateKeySnafu { path: private_key })?,
+    ))
+    .context(ReadPrivateKeySnafu { path: private_key })?
+    .context(MissingPrivateKeySnafu { path: private_key })?;
+
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certificate_chain, key)
+        .context(InvalidTlsConfigSnafu)?;
+    // Only HTTP/1.1 is served, so the Kubernetes API server must not negotiate HTTP/2
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    acceptor: TlsAcceptor,
+    client: Client,
+    product_config: Arc<ProductConfigManager>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let stream = acceptor.accept(stream).await?;
+    let service = service_fn(move |request: Request<Incoming>| {
+        let client = client.clone();
+        let product_config = product_config.clone();
+        async move { Ok::<_, Infallible>(handle(request, &client, &product_config).await) }
+    });
+    http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await?;
+    Ok(())
+}
+
+async fn handle(
+    request: Request<Incoming>,
+    client: &Client,
+    product_config: &ProductConfigManager,
+) -> Response<String> {
+    if request.method() != Method::POST {
+        return status_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    let path = request.uri().path().to_owned();
+    match path.as_str() {
+        CONVERSION_PATH => match read_json::<ConversionReview>(request).await {
+            Ok(review) => json_response(&conversion::convert(review)),
+            Err(response) => response,
+        },
+        VALIDATION_PATH => match read_json::<AdmissionReview<DynamicObject>>(request).await {
+            Ok(review) => json_response(&validation::validate(review, product_config)),
+            Err(response) => response,
+        },
+        SCALE_VALIDATION_PATH => match read_json::<AdmissionReview<DynamicObject>>(request).await {
+            Ok(review) => json_response(&validation::validate_scale(review, client).await),
+            Err(response) => response,
+        },
+        _ => status_response(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Reads the body of the request as JSON, or returns the response rejecting the request
+async fn read_json<T: DeserializeOwned>(request: Request<Incoming>) -> Result<T, Response<String>> {
+    let body = match request.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(error) => {
+            tracing::debug!(%error, "failed to read a webhook request");
+            return Err(status_response(StatusCode::BAD_REQUEST));
+        }
+    };
+    serde_json::from_slice(&body).map_err(|error| {
+        tracing::warn!(%error, "failed to parse a webhook request");
+        status_response(StatusCode::BAD_REQUEST)
+    })
+}
+
+fn json_response(body: &impl Serialize) -> Response<String> {
+    match serde_json::to_string(body) {
+        Ok(body) => {
+            let mut response = Response::new(body);
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            );
+            response
+        }
+        Err(error) => {
+            tracing::error!(%error, "failed to serialize a webhook response");
+            status_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn status_response(status: StatusCode) -> Response<String> {
+    let mut response = Response::new(String::new());
+    *response.status_mut() = status;
+    response
+}
+
+/// Points the conversion of the `HelloCluster` CRD to the webhook Service
+async fn register_conversion_webhook(
+    client: &Client,
+    ca_bundle: &ByteString,
+    service_name: &str,
+    service_namespace: &str,
+    service_port: i32,
+) -> Result<(), Error> {
+    let conversion = CustomResourceConversion {
+        strategy: "Webhook".to_owned(),
+        webhook: Some(WebhookConversion {
+            client_config: Some(WebhookClientConfig {
+                ca_bundle: Some(ca_bundle.clone()),
+                service: Some(ServiceReference {
+                    name: service_name.to_owned(),
+                    namespace: service_namespace.to_owned(),
+                    path: Some(CONVERSION_PATH.to_owned()),
+                    port: Some(service_port),
+                }),
+                url: None,
+            }),
+            conversion_review_versions: vec!["v1".to_owned()],
+        }),
+    };
+    let patch = serde_json::json!({
+        "spec": {
+            "conversion": serde_json::to_value(conversion).context(SerializeCrdPatchSnafu)?,
+        },
+    });
+
+    let crd_name = HelloCluster::crd_name();
+    Api::<CustomResourceDefinition>::all(client.as_kube_client())
+        .patch(crd_name, &PatchParams::default(), &Patch::Merge(&patch))
+        .await
+        .with_context(|_| PatchCrdSnafu { crd_name })?;
+    tracing::info!(crd_name, "registered the conversion webhook");
+
+    Ok(())
+}
@@ -0,0 +1,351 @@
+//! Rejects invalid `HelloCluster`s at admission time, instead of only failing the reconciliation.
+use std::error::Error as StdError;
+
+use product_config::ProductConfigManager;
+use stackable_operator::{
+    client::Client,
+    kube::core::{
+        admission::{AdmissionRequest, AdmissionResponse, AdmissionReview, Operation},
+        DynamicObject,
+    },
+};
+
+use crate::{
+    controller::validate_hello_cluster,
+    crd::{v1alpha2::SCALE_ROLE_GROUP, HelloCluster, HelloRole, HelloWorkload},
+};
+
+/// Answers an `AdmissionReview` of the Kubernetes API server.
+///
+/// The same checks as at the start of the reconciliation are run, so that a `HelloCluster`
+/// passing the admission does not fail because of its own configuration later on.
+pub fn validate(
+    review: AdmissionReview<DynamicObject>,
+    product_config: &ProductConfigManager,
+) -> AdmissionReview<DynamicObject> {
+    let request: AdmissionRequest<DynamicObject> = match review.try_into() {
+        Ok(request) => request,
+        Err(error) => {
+            tracing::warn!(%error, "received an invalid AdmissionReview");
+            return AdmissionResponse::invalid(error.to_string()).into_review();
+        }
+    };
+    let response = AdmissionResponse::from(&request);
+
+    let object = match (&request.operation, request.object) {
+        (Operation::Create | Operation::Update, Some(object)) => object,
+        _ => return response.into_review(),
+    };
+
+    let hello = match parse_hello_cluster(object) {
+        Ok(hello) => hello,
+        Err(error) => {
+            return response
+                .deny(format!("invalid HelloCluster: {error}"))
+                .into_review()
+        }
+    };
+
+    if let Err(error) = validate_hello_cluster(&hello, product_config) {
+        return response.deny(error_chain(&error)).into_review();
+    }
+
+    // An old object which cannot be parsed anymore cannot be compared, but the new object is
+    // valid nevertheless
+    let old_hello = match request.operation {
+        Operation::Update => request
+            .old_object
+            .and_then(|old_object| parse_hello_cluster(old_object).ok()),
+        _ => None,
+    };
+    match old_hello.map(|old_hello| validate_unchanged_pod_management_policies(&old_hello, &hello))
+    {
+        Some(Err(message)) => response.deny(message),
+        _ => response,
+    }
+    .into_review()
+}
+
+fn parse_hello_cluster(object: DynamicObject) -> serde_json::Result<HelloCluster> {
+    serde_json::to_value(object).and_then(serde_json::from_value)
+}
+
+/// The pod management policy of a StatefulSet cannot be changed, so a change would fail every
+/// following reconciliation.
+fn validate_unchanged_pod_management_policies(
+    old_hello: &HelloCluster,
+    hello: &HelloCluster,
+) -> Result<(), String> {
+    let server_role = HelloRole::Server;
+    for role_group_name in hello.spec.servers.role_groups.keys() {
+        if !old_hello
+            .spec
+            .servers
+            .role_groups
+            .contains_key(role_group_name)
+        {
+            continue;
+        }
+        // Invalid configs were already rejected by the validation of the new object and were
+        // never applied for the old object
+        let (Ok(old_config), Ok(config)) = (
+            old_hello.merged_config(
+                &server_role,
+                &old_hello.server_rolegroup_ref(role_group_name),
+            ),
+            hello.merged_config(&server_role, &hello.server_rolegroup_ref(role_group_name)),
+        ) else {
+            continue;
+        };
+
+        // Deployments ignore the policy and a changed workload creates a new StatefulSet
+        if old_config.workload == HelloWorkload::StatefulSet
+            && config.workload == HelloWorkload::StatefulSet
+            && old_config.pod_management_policy != config.pod_management_policy
+        {
+            return Err(format!(
+                "the podManagementPolicy of the role group {role_group_name:?} cannot be changed \
+                 from {old} to {new}; remove the role group and add it again, or create a new \
+                 role group instead",
+                old = old_config.pod_management_policy,
+                new = config.pod_management_policy,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Answers an `AdmissionReview` of the `scale` subresource.
+///
+/// The Kubernetes API server writes the replicas to the fixed path of the
+/// [`SCALE_ROLE_GROUP`], creating the role group if it does not exist. Scaling is therefore only
+/// allowed if the servers consist of exactly this role group, which is also the only case in which
+/// the replicas are reported to the subresource. Autoscaled role groups cannot be scaled either,
+/// as their replicas are left to the HorizontalPodAutoscaler and the written replicas would be
+/// ignored.
+pub async fn validate_scale(
+    review: AdmissionReview<DynamicObject>,
+    client: &Client,
+) -> AdmissionReview<DynamicObject> {
+    let request: AdmissionRequest<DynamicObject> = match review.try_into() {
+        Ok(request) => request,
+        Err(error) => {
+            tracing::warn!(%error, "received an invalid AdmissionReview");
+            return AdmissionResponse::invalid(error.to_string()).into_review();
+        }
+    };
+    let response = AdmissionResponse::from(&request);
+
+    let namespace = request.namespace.as_deref().unwrap_or_default();
+    match client.get::<HelloCluster>(&request.name, namespace).await {
+        Ok(hello) => match validate_scale_target(&hello) {
+            Ok(()) => response,
+            Err(message) => response.deny(message),
+        },
+        Err(error) => response.deny(format!(
+            "failed to check the scaled HelloCluster: {}",
+            error_chain(&error)
+        )),
+    }
+    .into_review()
+}
+
+fn validate_scale_target(hello: &HelloCluster) -> Result<(), String> {
+    let role_groups = &hello.spec.servers.role_groups;
+    if role_groups.len() != 1 || !role_groups.contains_key(SCALE_ROLE_GROUP) {
+        let mut role_group_names = role_groups.keys().collect::<Vec<_>>();
+        role_group_names.sort();
+        return Err(format!(
+            "only servers consisting of the single role group {SCALE_ROLE_GROUP:?} can be scaled \
+             via the scale subresource, but the role groups are {role_group_names:?}; change the \
+             replicas of the role groups in the spec instead"
+        ));
+    }
+
+    // An invalid config fails the reconciliation anyway, so it does not prevent scaling
+    let autoscaled = hello
+        .merged_config(
+            &HelloRole::Server,
+            &hello.server_rolegroup_ref(SCALE_ROLE_GROUP),
+        )
+        .is_ok_and(|config| config.autoscaling.is_some());
+    if autoscaled {
+        return Err(format!(
+            "the role group {SCALE_ROLE_GROUP:?} is scaled by a HorizontalPodAutoscaler, so its \
+             replicas cannot be set via the scale subresource; change config.autoscaling instead"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Joins the error with all its sources, so that the user sees the actual cause
+fn error_chain(error: &dyn StdError) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    fn object(servers: &str) -> Value {
+        serde_yaml::from_str(&format!(
+            r#"
+            apiVersion: hello-world.stackable.tech/v1alpha2
+            kind: HelloCluster
+            metadata:
+              name: hello-world
+              namespace: default
+              uid: 0b3a7a7e-0b1b-4a5f-9b5e-6d1b8a0c2f11
+            spec:
+              image:
+                productVersion: 0.0.1
+              clusterConfig:
+                greeting:
+                  recipient: Stackable
+                  color: blue
+              servers: {servers}
+            "#
+        ))
+        .expect("illegal test input")
+    }
+
+    fn hello(servers: &str) -> HelloCluster {
+        serde_json::from_value(object(servers)).expect("illegal test input")
+    }
+
+    fn review(servers: &str) -> AdmissionReview<DynamicObject> {
+        serde_json::from_value(json!({
+            "apiVersion": "admission.k8s.io/v1",
+            "kind": "AdmissionReview",
+            "request": {
+                "uid": "5c2a0c0e-8f0f-4a4e-9b7e-3d6c2d1f7a90",
+                "kind": {
+                    "group": "hello-world.stackable.tech",
+                    "version": "v1alpha2",
+                    "kind": "HelloCluster",
+                },
+                "resource": {
+                    "group": "hello-world.stackable.tech",
+                    "version": "v1alpha2",
+                    "resource": "hello-world-clusters",
+                },
+                "name": "hello-world",
+                "namespace": "default",
+                "operation": "CREATE",
+                "userInfo": {},
+                "object": object(servers),
+                "dryRun": false,
+            },
+        }))
+        .expect("illegal test input")
+    }
+
+    #[rstest]
+    #[case::valid("{roleGroups: {default: {replicas: 1}}}", None)]
+    #[case::invalid_color(
+        "{roleGroups: {default: {replicas: 1, config: {greeting: {color: bleu}}}}}",
+        Some("the color \"bleu\" is not a valid CSS color")
+    )]
+    #[case::markup_in_config_overrides(
+        "{configOverrides: {application.properties: {greeting.recipient: <b>you</b>}}, roleGroups: {default: {replicas: 1}}}",
+        Some("must not contain the markup character '<'")
+    )]
+    #[case::invalid_graceful_shutdown_timeout(
+        "{config: {gracefulShutdownTimeout: forever}, roleGroups: {default: {replicas: 1}}}",
+        Some("invalid HelloCluster")
+    )]
+    fn test_validate(#[case] servers: &str, #[case] expected_denial: Option<&str>) {
+        let product_config =
+            ProductConfigManager::from_yaml_file("../../deploy/config-spec/properties.yaml")
+                .unwrap();
+
+        let response = serde_json::to_value(validate(review(servers), &product_config)).unwrap();
+
+        assert_eq!(
+            response["response"]["uid"],
+            "5c2a0c0e-8f0f-4a4e-9b7e-3d6c2d1f7a90"
+        );
+        match expected_denial {
+            None => assert_eq!(response["response"]["allowed"], true),
+            Some(expected_denial) => {
+                assert_eq!(response["response"]["allowed"], false);
+                let message = response["response"]["status"]["message"].as_str().unwrap();
+                assert!(
+                    message.contains(expected_denial),
+                    "{message:?} does not contain {expected_denial:?}"
+                );
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::single_default_role_group("{roleGroups: {default: {replicas: 1}}}", true)]
+    #[case::other_role_group("{roleGroups: {primary: {replicas: 1}}}", false)]
+    #[case::additional_role_group(
+        "{roleGroups: {default: {replicas: 1}, secondary: {replicas: 1}}}",
+        false
+    )]
+    #[case::autoscaled_role_group(
+        "{roleGroups: {default: {config: {autoscaling: {maxReplicas: 3}}}}}",
+        false
+    )]
+    #[case::autoscaled_role(
+        "{config: {autoscaling: {maxReplicas: 3}}, roleGroups: {default: {replicas: 1}}}",
+        false
+    )]
+    fn test_validate_scale_target(#[case] servers: &str, #[case] expected_allowed: bool) {
+        assert_eq!(
+            validate_scale_target(&hello(servers)).is_ok(),
+            expected_allowed
+        );
+    }
+
+    #[rstest]
+    #[case::unchanged(
+        "{roleGroups: {default: {replicas: 1}}}",
+        "{roleGroups: {default: {replicas: 3}}}",
+        true
+    )]
+    #[case::changed_in_role_group(
+        "{roleGroups: {default: {replicas: 1}}}",
+        "{roleGroups: {default: {replicas: 1, config: {podManagementPolicy: OrderedReady}}}}",
+        false
+    )]
+    #[case::changed_in_role(
+        "{roleGroups: {default: {replicas: 1}}}",
+        "{config: {podManagementPolicy: OrderedReady}, roleGroups: {default: {replicas: 1}}}",
+        false
+    )]
+    #[case::new_role_group(
+        "{roleGroups: {default: {replicas: 1}}}",
+        "{roleGroups: {default: {replicas: 1}, ordered: {replicas: 1, config: {podManagementPolicy: OrderedReady}}}}",
+        true
+    )]
+    #[case::deployment(
+        "{config: {workload: Deployment}, roleGroups: {default: {replicas: 1}}}",
+        "{config: {workload: Deployment, podManagementPolicy: OrderedReady}, roleGroups: {default: {replicas: 1}}}",
+        true
+    )]
+    fn test_validate_unchanged_pod_management_policies(
+        #[case] old_servers: &str,
+        #[case] servers: &str,
+        #[case] expected_allowed: bool,
+    ) {
+        assert_eq!(
+            validate_unchanged_pod_management_policies(&hello(old_servers), &hello(servers))
+                .is_ok(),
+            expected_allowed
+        );
+    }
+}